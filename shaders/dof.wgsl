@@ -0,0 +1,66 @@
+const GOLDEN_ANGLE: f32 = 2.399963229728653;
+const SAMPLE_COUNT: i32 = 24;
+const COC_PER_BLOCK: f32 = 0.2; // blur radius in pixels per block away from the focus
+const MAX_COC: f32 = 8.0;
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_proj_skybox_inverse: mat4x4<f32>,
+    pos: vec3<f32>,
+    focus_distance: f32,
+    near: f32,
+    far: f32,
+};
+
+@group(1) @binding(0) var<uniform> camera: CameraUniform;
+
+@group(0) @binding(0) var scene_texture: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+@group(0) @binding(2) var depth_texture: texture_depth_multisampled_2d;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> @builtin(position) vec4<f32> {
+    var positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -3.0),
+        vec2<f32>(3.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    return vec4<f32>(positions[vi], 0.0, 1.0);
+}
+
+// inverse of the [0, 1] depth of a right-handed perspective projection
+fn linear_depth(pixel: vec2<i32>) -> f32 {
+    let depth = textureLoad(depth_texture, pixel, 0);
+    return camera.near * camera.far / (camera.far - depth * (camera.far - camera.near));
+}
+
+// circle of confusion radius in pixels
+fn coc(pixel: vec2<i32>) -> f32 {
+    return min(abs(linear_depth(pixel) - camera.focus_distance) * COC_PER_BLOCK, MAX_COC);
+}
+
+@fragment
+fn fs_main(@builtin(position) frag_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(scene_texture));
+    let max_pixel = vec2<i32>(size) - 1;
+    let radius = coc(vec2<i32>(frag_pos.xy));
+
+    var color = textureSampleLevel(scene_texture, scene_sampler, frag_pos.xy / size, 0.0).rgb;
+    var total_weight = 1.0;
+    if radius < 0.5 {
+        return vec4<f32>(color, 1.0);
+    }
+
+    // spiral over the disc so the samples spread evenly whatever the radius
+    for (var i = 1; i < SAMPLE_COUNT; i++) {
+        let r = radius * sqrt(f32(i) / f32(SAMPLE_COUNT));
+        let theta = f32(i) * GOLDEN_ANGLE;
+        let position = frag_pos.xy + vec2<f32>(cos(theta), sin(theta)) * r;
+        let pixel = clamp(vec2<i32>(position), vec2<i32>(0), max_pixel);
+        // sharp samples don't bleed onto their blurry neighbors
+        let weight = clamp(coc(pixel) - r + 1.0, 0.0, 1.0);
+        color += textureSampleLevel(scene_texture, scene_sampler, position / size, 0.0).rgb * weight;
+        total_weight += weight;
+    }
+    return vec4<f32>(color / total_weight, 1.0);
+}
@@ -0,0 +1,53 @@
+const GRAPH_SIZE: vec2<f32> = vec2<f32>(120.0, 40.0);
+const FRAME_COUNT: f32 = 60.0;
+const FULL_BAR_MS: f32 = 50.0;
+
+struct FrameGraphUniform {
+    origin: vec2<f32>, // bottom left corner, in pixels
+    screen_size: vec2<f32>,
+    frame_times: array<vec4<f32>, 15>, // milliseconds, oldest first, packed by 4
+};
+
+@group(0) @binding(0)
+var<uniform> graph: FrameGraphUniform;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+// one quad per frame
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32, @builtin(instance_index) ii: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vi];
+    let frame_time = graph.frame_times[ii / 4u][ii % 4u];
+
+    let bar_width = GRAPH_SIZE.x / FRAME_COUNT;
+    let bar_height = min(frame_time / FULL_BAR_MS, 1.0) * GRAPH_SIZE.y;
+    let pixel = vec2<f32>(
+        graph.origin.x + (f32(ii) + corner.x) * bar_width,
+        graph.origin.y - corner.y * bar_height,
+    );
+    let ndc = vec2<f32>(pixel.x / graph.screen_size.x * 2.0 - 1.0, 1.0 - pixel.y / graph.screen_size.y * 2.0);
+
+    var color = vec3<f32>(1.0, 0.1, 0.1);
+    if frame_time < 16.0 {
+        color = vec3<f32>(0.1, 0.9, 0.1);
+    } else if frame_time < 33.0 {
+        color = vec3<f32>(1.0, 0.9, 0.1);
+    }
+    return VertexOutput(vec4<f32>(ndc, 0.0, 1.0), color);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,28 @@
+const BORDER: f32 = 0.01;
+
+@group(0) @binding(0) var minimap_texture: texture_2d<f32>;
+@group(0) @binding(1) var minimap_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// fullscreen triangle, the viewport restricts it to the minimap corner
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -3.0),
+        vec2<f32>(3.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let p = pos[vi];
+    return VertexOutput(vec4<f32>(p, 0.0, 1.0), vec2<f32>(0.5 + 0.5 * p.x, 0.5 - 0.5 * p.y));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(minimap_texture, minimap_sampler, in.uv).rgb;
+    let edge = min(min(in.uv.x, in.uv.y), min(1.0 - in.uv.x, 1.0 - in.uv.y));
+    return vec4<f32>(select(color, vec3<f32>(0.0), edge < BORDER), 1.0);
+}
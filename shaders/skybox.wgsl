@@ -1,5 +1,11 @@
 const PI: f32 = 3.141592653589793;
 const TAU: f32 = 6.283185307179586;
+const HORIZON_SHARPNESS: f32 = 6.0;
+// must match TWILIGHT in state.rs
+const TWILIGHT_START: f32 = -0.15;
+const TWILIGHT_END: f32 = 0.3;
+const SUN_COS_RADIUS: f32 = 0.9995;
+const SUN_COLOR: vec3<f32> = vec3<f32>(1.0, 0.95, 0.8);
 
 struct CameraUniform {
     view_proj: mat4x4<f32>,
@@ -9,6 +15,24 @@ struct CameraUniform {
 
 @group(1) @binding(0) var<uniform> camera: CameraUniform;
 
+struct FogUniform {
+    fog_color: vec3<f32>,
+    fog_start: f32,
+    sky_color: vec3<f32>,
+    fog_end: f32,
+};
+
+@group(2) @binding(0) var<uniform> fog: FogUniform;
+
+struct SkyUniform {
+    sun_direction: vec3<f32>,
+    time: f32, // fraction of the day, 0 at midnight
+    horizon_color: vec4<f32>,
+    zenith_color: vec4<f32>,
+};
+
+@group(3) @binding(0) var<uniform> sky: SkyUniform;
+
 @group(0) @binding(0) var sky_texture: texture_2d<f32>;
 @group(0) @binding(1) var sky_sampler: sampler;
 
@@ -49,10 +73,35 @@ fn pano_uv(dir: vec3<f32>) -> vec2<f32> {
     return vec2<f32>(u, v);
 }
 
+// the stars turn with the sun, around the north-south axis
+fn rotate_with_sun(dir: vec3<f32>) -> vec3<f32> {
+    let angle = sky.time * TAU;
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec3<f32>(c * dir.x + s * dir.z, dir.y, c * dir.z - s * dir.x);
+}
+
+fn night_sky(dir: vec3<f32>) -> vec3<f32> {
+    let c = textureSample(sky_texture, sky_sampler, pano_uv(rotate_with_sun(dir)));
+    // tint the horizon with the color of the current biome
+    let horizon = pow(1.0 - abs(dir.z), HORIZON_SHARPNESS);
+    return mix(c.rgb, fog.sky_color, horizon);
+}
+
+fn day_sky(dir: vec3<f32>) -> vec3<f32> {
+    let gradient = mix(sky.horizon_color.rgb, sky.zenith_color.rgb, sqrt(max(dir.z, 0.0)));
+    // rayleigh phase function, the sky is brighter toward and away from the sun
+    let cos_theta = dot(dir, sky.sun_direction);
+    let scattered = gradient * 0.75 * (1.0 + cos_theta * cos_theta);
+    let sun = smoothstep(SUN_COS_RADIUS - 0.0005, SUN_COS_RADIUS, cos_theta);
+    return mix(scattered, SUN_COLOR, sun);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let dir = world_dir_from_ndc(in.ndc);
-    let uv = pano_uv(dir);
-    let c = textureSample(sky_texture, sky_sampler, uv);
-    return vec4<f32>(c.rgb, 1.0);
+    // textureSample needs uniform control flow, so both skies are always computed
+    let night_color = night_sky(dir);
+    let night = 1.0 - smoothstep(TWILIGHT_START, TWILIGHT_END, sky.sun_direction.z);
+    return vec4<f32>(mix(day_sky(dir), night_color, night), 1.0);
 }
\ No newline at end of file
@@ -7,11 +7,31 @@ struct CameraUniform {
 @group(1) @binding(0)
 var<uniform> camera: CameraUniform;
 
+struct FogUniform {
+    fog_color: vec3<f32>,
+    fog_start: f32,
+    sky_color: vec3<f32>,
+    fog_end: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> fog: FogUniform;
+
+struct WaterUniform {
+    color: vec4<f32>,
+    time: f32,
+};
+
+@group(3) @binding(0)
+var<uniform> water: WaterUniform;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) tex_coords: vec2<f32>,
     @location(3) atlas_offset: vec2<u32>,
+    @location(4) light: f32,
+    @location(5) ao: f32,
 }
 
 struct VertexOutput {
@@ -19,6 +39,9 @@ struct VertexOutput {
     @location(0) tex_coords: vec2<f32>,
     @location(1) atlas_offset: vec2<u32>,
     @location(2) dist: f32,
+    @location(3) light: f32,
+    @location(4) ao: f32,
+    @location(5) world_position: vec3<f32>,
 }
 
 @vertex
@@ -28,6 +51,9 @@ fn vs_main(model: VertexInput) -> VertexOutput {
     out.atlas_offset = model.atlas_offset;
     out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
     out.dist = distance(model.position.xyz, camera.pos);
+    out.light = model.light;
+    out.ao = model.ao;
+    out.world_position = model.position;
     return out;
 }
 
@@ -58,8 +84,15 @@ fn t1(uv: vec2<f32>) -> vec2<f32> {
     return vec2(uv.x / 16.0 + 0.9375, uv.y / 16.0 + 0.9375);
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+const TRANSPARENT_ALPHA: f32 = 0.6;
+const ALPHA_CUTOUT: f32 = 0.5;
+const RIPPLE_STRENGTH: f32 = 0.08;
+const RIPPLE_FREQUENCY: f32 = 1.7;
+const RIPPLE_SPEED: f32 = 1.3;
+const WIREFRAME_COLOR: vec3<f32> = vec3(0.0, 0.0, 0.0);
+
+// the tint is applied before the fog
+fn shade(in: VertexOutput, tint: vec4<f32>) -> vec4<f32> {
     var uv = (fract(in.tex_coords) + vec2<f32>(in.atlas_offset)) / ATLAS_SHAPE;
 
     let d = max(in.dist, 1e-5);
@@ -77,9 +110,46 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         default: { a = t1(uv);  b = t1(uv); }
     }
 
-    return mix(
+    let color = mix(
         textureSample(t_diffuse, s_diffuse, a),
         textureSample(t_diffuse, s_diffuse, b),
         fract(lod),
     );
-}
\ No newline at end of file
+    let fog_factor = clamp((in.dist - fog.fog_start) / (fog.fog_end - fog.fog_start), 0.0, 1.0);
+    let lit = color.rgb * tint.rgb * in.light * in.ao;
+    return vec4<f32>(mix(lit, fog.fog_color, fog_factor), color.a * tint.a);
+}
+
+// the holes in the leaves are cut out instead of blended
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in, vec4<f32>(1.0));
+    if color.a < ALPHA_CUTOUT {
+        discard;
+    }
+    return vec4<f32>(color.rgb, 1.0);
+}
+
+@fragment
+fn fs_transparent(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in, vec4<f32>(1.0, 1.0, 1.0, TRANSPARENT_ALPHA));
+}
+
+// two crossing waves brighten and darken the surface as if its normal was moving
+fn ripple(pos: vec2<f32>) -> f32 {
+    let phase = water.time * RIPPLE_SPEED;
+    let a = sin(dot(pos, vec2<f32>(0.8, 0.6)) * RIPPLE_FREQUENCY + phase);
+    let b = sin(dot(pos, vec2<f32>(-0.5, 0.87)) * RIPPLE_FREQUENCY * 1.3 + phase * 0.7);
+    return 1.0 + RIPPLE_STRENGTH * 0.5 * (a + b);
+}
+
+@fragment
+fn fs_water(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tint = water.color.rgb * ripple(in.world_position.xy);
+    return shade(in, vec4<f32>(tint, water.color.a));
+}
+
+@fragment
+fn fs_wireframe(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(WIREFRAME_COLOR, 1.0);
+}
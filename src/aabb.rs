@@ -12,6 +12,13 @@ impl AABB {
         Self { min, max }
     }
 
+    pub fn merge(a: &Self, b: &Self) -> Self {
+        Self {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
     pub fn center(&self) -> Vec3 {
         (self.min + self.max) * 0.5
     }
@@ -19,4 +26,25 @@ impl AABB {
     pub fn extents(&self) -> Vec3 {
         (self.max - self.min) * 0.5
     }
+
+    // boxes that only touch don't intersect, so the player can stand on a block
+    pub fn intersects_aabb(&self, other: &Self) -> bool {
+        self.min.cmplt(other.max).all() && other.min.cmplt(self.max).all()
+    }
+
+    #[expect(dead_code)] // TODO: collisions
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.min.cmple(p).all() && p.cmple(self.max).all()
+    }
+
+    // slab method, distance along `direction` to the first hit (0 when starting inside)
+    #[expect(dead_code)] // TODO: skip empty chunks in find_block_in_dir
+    pub fn ray_intersect(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let inverse = direction.recip();
+        let t0 = (self.min - origin) * inverse;
+        let t1 = (self.max - origin) * inverse;
+        let t_near = t0.min(t1).max_element();
+        let t_far = t0.max(t1).min_element();
+        (t_near <= t_far && t_far >= 0.0).then_some(t_near.max(0.0))
+    }
 }
@@ -1,39 +1,75 @@
 use {
     crate::{
         Args,
-        chunk::CHUNK_WIDTH,
-        coords::{ChunkCoords, camera_to_chunk_coords, split_coords},
-        state::{MEMORY_DISTANCE, State},
-        world::World,
+        config::WorldConfig,
+        coords::{ChunkCoords, WorldCoords, camera_to_chunk_coords, camera_to_world_coords},
+        shader::ShaderWatcher,
+        state::{FRAME_GRAPH_LEN, MEMORY_DISTANCE, State},
+        world::{MAX_DELETE_DISTANCE, World},
     },
     std::{
+        collections::VecDeque,
+        fs,
         sync::Arc,
-        time::{Duration, Instant},
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     },
     winit::{
         application::ApplicationHandler,
         dpi::{PhysicalPosition, PhysicalSize},
-        event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent},
+        event::{DeviceEvent, DeviceId, ElementState, KeyEvent, MouseScrollDelta, WindowEvent},
         event_loop::ActiveEventLoop,
-        keyboard::{KeyCode, PhysicalKey},
+        keyboard::{KeyCode, ModifiersState, PhysicalKey},
         window::{Fullscreen, Window, WindowAttributes, WindowId},
     },
 };
 
+const SPAWN_PRELOAD_RADIUS: i32 = 5;
+const PIXELS_PER_LINE: f32 = 40.0; // touchpads scroll in pixels
+
 pub struct Application {
     args: Args,
     window_attributes: WindowAttributes,
     window: Option<Arc<Window>>,
     state: Option<State>,
     world: World,
+    shader_watcher: Option<ShaderWatcher>,
     last_chunk: Option<ChunkCoords>,
+    fill_corner: Option<WorldCoords>,
+    modifiers: ModifiersState,
     last_render: Instant,
     last_fps_log: Instant,
     frames_since_log: u32,
+    frame_times: VecDeque<f32>, // milliseconds
 }
 
 impl Application {
     pub fn new(args: Args) -> Self {
+        let config = args
+            .config
+            .as_ref()
+            .map_or_else(WorldConfig::default, |path| {
+                WorldConfig::load(path).unwrap_or_else(|err| {
+                    log::error!("failed to load {}: {err}", path.display());
+                    WorldConfig::default()
+                })
+            });
+        let kind = args.generator_kind();
+        let save_file = args.save_file();
+        let world = if save_file.exists() {
+            World::load(&save_file, args.seed, args.voronoi_temp, &config, kind).unwrap_or_else(
+                |err| {
+                    log::error!("failed to load {}: {err}", save_file.display());
+                    World::new(args.seed, args.voronoi_temp, &config, kind)
+                },
+            )
+        } else {
+            World::new(args.seed, args.voronoi_temp, &config, kind)
+        };
+
+        let shader_watcher = ShaderWatcher::new()
+            .inspect_err(|err| log::warn!("shader hot-reload disabled: {err}"))
+            .ok();
+
         let mut window_attributes = Window::default_attributes()
             .with_title("ft_vox")
             .with_resizable(true)
@@ -47,14 +83,26 @@ impl Application {
             window_attributes,
             window: None,
             state: None,
-            world: World::new(args.seed),
+            world,
+            shader_watcher,
             last_chunk: None,
+            fill_corner: None,
+            modifiers: ModifiersState::empty(),
             last_render: Instant::now(),
             last_fps_log: Instant::now(),
             frames_since_log: 0,
+            frame_times: VecDeque::with_capacity(FRAME_GRAPH_LEN),
             args,
         }
     }
+
+    fn save_world(&self) {
+        let save_file = self.args.save_file();
+        match self.world.save(&save_file) {
+            Ok(()) => log::info!("saved world to {}", save_file.display()),
+            Err(err) => log::error!("failed to save {}: {err}", save_file.display()),
+        }
+    }
 }
 
 impl ApplicationHandler for Application {
@@ -65,7 +113,15 @@ impl ApplicationHandler for Application {
                 .unwrap(),
         );
         window.set_cursor_visible(false);
-        let state = pollster::block_on(State::new(Arc::clone(&window), &self.args));
+        let spawn_point = self
+            .world
+            .find_spawn_point(self.args.spawn_x, self.args.spawn_y);
+        // the first frame already shows the surroundings, the rest streams in afterwards
+        let preloaded = self
+            .world
+            .preload_circle(camera_to_chunk_coords(spawn_point), SPAWN_PRELOAD_RADIUS);
+        log::info!("preloaded {preloaded} chunks around spawn");
+        let state = pollster::block_on(State::new(Arc::clone(&window), &self.args, spawn_point));
 
         self.window = Some(window);
         self.state = Some(state);
@@ -79,6 +135,17 @@ impl ApplicationHandler for Application {
                 camera_controller.process_mouse_motion(dx as f32, dy as f32);
                 state.update_crosshair(&self.world);
             }
+            DeviceEvent::MouseWheel { delta } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                if self.modifiers.alt_key() {
+                    state.scroll_hotbar(lines);
+                } else {
+                    camera_controller.process_scroll(lines);
+                }
+            }
             DeviceEvent::Button {
                 button,
                 state: button_state,
@@ -95,32 +162,26 @@ impl ApplicationHandler for Application {
                             self.world.delete_center_block(&state.camera)
                         {
                             log::debug!("Deleted {block:?}");
-
-                            let ((cx, cy), (bx, by, _)) = split_coords(world_coords).unwrap();
-
-                            state.chunks_to_rerender.insert((cx, cy));
-
-                            // rerender neighbors if on the edge
-                            if bx == 0 {
-                                state.chunks_to_rerender.insert((cx - 1, cy));
-                            } else if bx == CHUNK_WIDTH - 1 {
-                                state.chunks_to_rerender.insert((cx + 1, cy));
-                            }
-                            if by == 0 {
-                                state.chunks_to_rerender.insert((cx, cy - 1));
-                            } else if by == CHUNK_WIDTH - 1 {
-                                state.chunks_to_rerender.insert((cx, cy + 1));
-                            }
+                            state.schedule_rerender(world_coords);
                         }
                     }
                 }
+                2 if button_state.is_pressed() => {
+                    if let Some(world_coords) = self
+                        .world
+                        .place_block_in_dir(&state.camera, state.selected_block)
+                    {
+                        log::debug!("Placed {:?}", state.selected_block);
+                        state.schedule_rerender(world_coords);
+                    }
+                }
                 _ => {}
             },
             _ => {}
         }
     }
 
-    #[expect(clippy::too_many_lines)]
+    #[expect(clippy::too_many_lines, clippy::cognitive_complexity)] // one arm per key binding
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -161,6 +222,196 @@ impl ApplicationHandler for Application {
             } => {
                 state.toggle_show_fps();
             }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::F4),
+                        ..
+                    },
+                ..
+            } => {
+                state.toggle_show_wireframe();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::F2),
+                        ..
+                    },
+                ..
+            } => {
+                state.toggle_frame_graph();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyZ),
+                        ..
+                    },
+                ..
+            } if self.modifiers.control_key() => {
+                state.undo_last_deletion(&mut self.world);
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyM),
+                        ..
+                    },
+                ..
+            } => {
+                state.toggle_minimap();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::F6),
+                        ..
+                    },
+                ..
+            } => {
+                state.toggle_depth_of_field();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::F5),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                let path = format!("screenshot_{timestamp}.png");
+                match fs::write(&path, state.take_screenshot()) {
+                    Ok(()) => log::info!("saved screenshot to {path}"),
+                    Err(err) => log::error!("failed to save {path}: {err}"),
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::F3),
+                        ..
+                    },
+                ..
+            } => {
+                state.toggle_show_debug();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyG),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                state.camera_controller.toggle_flying();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyX),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                let dirty_chunks = self.world.explode_center_block(&state.camera);
+                state.chunks_to_rerender.extend(dirty_chunks);
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(keycode @ (KeyCode::KeyB | KeyCode::KeyN)),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                // first press marks a corner, second press fills up to the targeted block
+                let Some((_, target, ..)) = self.world.find_block_in_dir(
+                    state.camera.position(),
+                    state.camera.direction(),
+                    MAX_DELETE_DISTANCE,
+                ) else {
+                    return;
+                };
+                match self.fill_corner.take() {
+                    None => {
+                        log::info!("fill corner set at {target:?}");
+                        self.fill_corner = Some(target);
+                    }
+                    Some(corner) => {
+                        let dirty_chunks = if keycode == KeyCode::KeyB {
+                            self.world
+                                .fill(&state.camera, corner, target, state.selected_block)
+                        } else {
+                            self.world.delete_region(corner, target)
+                        };
+                        state.chunks_to_rerender.extend(dirty_chunks);
+                        state.update_crosshair(&self.world);
+                    }
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::BracketRight),
+                        ..
+                    },
+                ..
+            } => {
+                state.camera_controller.increase_sensitivity();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::BracketLeft),
+                        ..
+                    },
+                ..
+            } => {
+                state.camera_controller.decrease_sensitivity();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Equal | KeyCode::NumpadAdd),
+                        ..
+                    },
+                ..
+            } => {
+                state.increase_render_distance(&mut self.world);
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Minus | KeyCode::NumpadSubtract),
+                        ..
+                    },
+                ..
+            } => {
+                state.decrease_render_distance(&mut self.world);
+            }
             WindowEvent::CloseRequested
             | WindowEvent::KeyboardInput {
                 event:
@@ -170,7 +421,10 @@ impl ApplicationHandler for Application {
                         ..
                     },
                 ..
-            } => event_loop.exit(),
+            } => {
+                self.save_world();
+                event_loop.exit();
+            }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
@@ -185,6 +439,9 @@ impl ApplicationHandler for Application {
                     .process_keyboard(element_state, keycode);
                 state.update_crosshair(&self.world);
             }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
             WindowEvent::Resized(physical_size) => {
                 log::info!("physical_size: {physical_size:?}");
                 state.resize(physical_size);
@@ -193,25 +450,44 @@ impl ApplicationHandler for Application {
                 let now = Instant::now();
                 let dt = now - self.last_render;
                 if dt >= Duration::from_millis(self.args.slow_frame_warning_ms) {
-                    log::warn!("frame took {}ms to generate", dt.as_millis());
+                    let WorldCoords { x, y, z } = camera_to_world_coords(state.camera.position());
+                    log::warn!(
+                        "frame took {}ms to generate at {x} / {y} / {z}",
+                        dt.as_millis()
+                    );
                 }
                 self.last_render = now;
+                if self.frame_times.len() == FRAME_GRAPH_LEN {
+                    self.frame_times.pop_front();
+                }
+                self.frame_times.push_back(dt.as_secs_f32() * 1000.0);
+                state.draw_frame_time_graph(self.frame_times.make_contiguous());
+
+                if let Some(shader_watcher) = &self.shader_watcher {
+                    for kind in shader_watcher.changed_shaders() {
+                        state.reload_shader(kind);
+                    }
+                }
 
-                state.update(dt);
+                self.world.advance_time(dt.as_secs_f32());
+                state.update(&mut self.world, dt);
 
                 state.rerender_chunks(&mut self.world);
 
                 let camera_chunk = camera_to_chunk_coords(state.camera.position());
                 if self.last_chunk == Some(camera_chunk) {
                     'preload_chunk: for i in 0..MEMORY_DISTANCE {
-                        for x in camera_chunk.0 - i..=camera_chunk.0 + i {
-                            for y in camera_chunk.1 - i..=camera_chunk.1 + i {
-                                let chunk_coords = (x, y);
+                        for dx in -i..=i {
+                            for dy in -i..=i {
+                                let chunk_coords = camera_chunk.offset(dx, dy);
                                 if !self.world.chunks.contains_key(&chunk_coords)
                                     || !state.chunk_render_data.contains_key(&chunk_coords)
                                 {
-                                    self.world.load_chunk(chunk_coords);
-                                    state.generate_chunk_mesh(&mut self.world, chunk_coords);
+                                    if self.world.is_neighborhood_loaded(chunk_coords) {
+                                        state.generate_chunk_mesh(&mut self.world, chunk_coords);
+                                    } else {
+                                        self.world.request_neighborhood(chunk_coords);
+                                    }
                                     break 'preload_chunk;
                                 }
                             }
@@ -220,6 +496,9 @@ impl ApplicationHandler for Application {
                 } else {
                     self.last_chunk = Some(camera_chunk);
                     state.update_chunks(&mut self.world);
+                    let position = state.camera.position();
+                    let biome = self.world.get_column_info_under(position).biome;
+                    window.set_title(&State::format_window_title(self.args.seed, position, biome));
                 }
                 self.world.discard_far_chunks(camera_chunk);
 
@@ -235,14 +514,15 @@ impl ApplicationHandler for Application {
                         if elapsed >= Duration::from_secs(1) {
                             let secs = elapsed.as_secs_f64();
                             state.fps = self.frames_since_log as f32 / secs as f32;
+                            state.biome = self
+                                .world
+                                .get_column_info_under(state.camera.position())
+                                .biome;
                             log::info!(
                                 "FPS: {:.1} | CHUNK: {:?} | BIOME: {:?}",
                                 state.fps,
                                 camera_chunk,
-                                self.world.determine_biome(&self.world.get_noise_values(
-                                    state.camera.position().x as i32,
-                                    state.camera.position().y as i32
-                                ))
+                                state.biome
                             );
                             self.frames_since_log = 0;
                             self.last_fps_log = Instant::now();
@@ -1,5 +1,12 @@
 use crate::block::BlockType;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BiomeType {
     Desert,
@@ -52,6 +59,59 @@ pub enum BiomeType {
     SavannaPlateau,
 }
 impl BiomeType {
+    pub const fn display_name(&self) -> &'static str {
+        match self {
+            Self::Desert => "Desert",
+            Self::Plains => "Plains",
+            Self::Forest => "Forest",
+            Self::Taiga => "Taiga",
+            Self::Swamp => "Swamp",
+            Self::Beach => "Beach",
+            Self::FrozenPeaks => "Frozen Peaks",
+            Self::Ocean => "Ocean",
+            Self::ColdOcean => "Cold Ocean",
+            Self::FrozenOcean => "Frozen Ocean",
+            Self::WarmOcean => "Warm Ocean",
+            Self::DeepOcean => "Deep Ocean",
+            Self::DeepColdOcean => "Deep Cold Ocean",
+            Self::DeepFrozenOcean => "Deep Frozen Ocean",
+            Self::River => "River",
+            Self::FrozenRiver => "Frozen River",
+            Self::Grove => "Grove",
+            Self::Mangrove => "Mangrove",
+            Self::StonyShore => "Stony Shore",
+            Self::StonyPeaks => "Stony Peaks",
+            Self::WindsweptSavanna => "Windswept Savanna",
+            Self::SnowySlopes => "Snowy Slopes",
+            Self::SnowyBeach => "Snowy Beach",
+            Self::JaggedPeaks => "Jagged Peaks",
+            Self::Badlands => "Badlands",
+            Self::ErodedBadlands => "Eroded Badlands",
+            Self::WoodedBadlands => "Wooded Badlands",
+            Self::Jungle => "Jungle",
+            Self::BambooJungle => "Bamboo Jungle",
+            Self::SparseJungle => "Sparse Jungle",
+            Self::Savanna => "Savanna",
+            Self::DarkForest => "Dark Forest",
+            Self::OldGrowthBirchForest => "Old Growth Birch Forest",
+            Self::BirchForest => "Birch Forest",
+            Self::SunflowerForest => "Sunflower Forest",
+            Self::FlowerForest => "Flower Forest",
+            Self::OldGrowthPineTaiga => "Old Growth Pine Taiga",
+            Self::OldGrowthSpruceTaiga => "Old Growth Spruce Taiga",
+            Self::SnowyTaiga => "Snowy Taiga",
+            Self::SnowyPlains => "Snowy Plains",
+            Self::IceSpikes => "Ice Spikes",
+            Self::WindsweptHills => "Windswept Hills",
+            Self::WindsweptForest => "Windswept Forest",
+            Self::WindsweptGravellyHills => "Windswept Gravelly Hills",
+            Self::PaleGarden => "Pale Garden",
+            Self::Meadow => "Meadow",
+            Self::CherryGrove => "Cherry Grove",
+            Self::SavannaPlateau => "Savanna Plateau",
+        }
+    }
+
     pub const fn get_surface_block(&self) -> BlockType {
         match self {
             Self::Swamp => BlockType::Dirt,
@@ -79,6 +139,24 @@ impl BiomeType {
         }
     }
 
+    // what the surface block lies on, soil under the plants and snow
+    pub const fn get_subsurface_block(&self) -> BlockType {
+        match self.get_surface_block() {
+            BlockType::Grass | BlockType::Snow | BlockType::WarpedNylium => BlockType::Dirt,
+            block => block,
+        }
+    }
+
+    // thickness of the surface block and the subsurface blocks below it
+    pub const fn subsurface_depth(&self) -> usize {
+        match self {
+            Self::Desert => 8,
+            Self::StonyPeaks => 3,
+            Self::Beach => 2,
+            _ => 5,
+        }
+    }
+
     pub const fn is_ocean(&self) -> bool {
         matches!(
             self,
@@ -89,6 +167,170 @@ impl BiomeType {
                 | Self::DeepColdOcean
                 | Self::DeepOcean
                 | Self::WarmOcean
+                | Self::River
+                | Self::FrozenRiver
+        )
+    }
+
+    pub const fn is_snowy(&self) -> bool {
+        matches!(
+            self,
+            Self::SnowyPlains
+                | Self::SnowyTaiga
+                | Self::SnowySlopes
+                | Self::SnowyBeach
+                | Self::FrozenPeaks
+                | Self::JaggedPeaks
+                | Self::Grove
+                | Self::IceSpikes
+                | Self::FrozenRiver
+                | Self::FrozenOcean
+                | Self::DeepFrozenOcean
         )
     }
+
+    pub const fn is_arid(&self) -> bool {
+        matches!(
+            self,
+            Self::Desert
+                | Self::Badlands
+                | Self::ErodedBadlands
+                | Self::WoodedBadlands
+                | Self::Savanna
+                | Self::SavannaPlateau
+                | Self::WindsweptSavanna
+        )
+    }
+
+    pub const fn precipitation(&self) -> Precipitation {
+        if self.is_snowy() {
+            Precipitation::Snow
+        } else if self.is_arid() {
+            Precipitation::None
+        } else {
+            Precipitation::Rain
+        }
+    }
+
+    // one tree every n columns on average
+    pub const fn tree_frequency(&self) -> Option<u64> {
+        match self {
+            Self::DarkForest => Some(12),
+            Self::Jungle | Self::BambooJungle => Some(15),
+            Self::Forest
+            | Self::BirchForest
+            | Self::OldGrowthBirchForest
+            | Self::FlowerForest
+            | Self::SunflowerForest => Some(40),
+            Self::Taiga
+            | Self::SnowyTaiga
+            | Self::OldGrowthPineTaiga
+            | Self::OldGrowthSpruceTaiga
+            | Self::WindsweptForest
+            | Self::Grove => Some(50),
+            Self::SparseJungle | Self::WoodedBadlands | Self::PaleGarden | Self::Swamp => Some(80),
+            Self::Plains
+            | Self::Meadow
+            | Self::CherryGrove
+            | Self::Savanna
+            | Self::SavannaPlateau => Some(300),
+            _ => None,
+        }
+    }
+
+    // srgb color of the biome on the minimap
+    pub const fn map_color(&self) -> [u8; 3] {
+        match self {
+            Self::DeepOcean | Self::DeepColdOcean | Self::DeepFrozenOcean => [16, 40, 110],
+            Self::Ocean | Self::ColdOcean | Self::River => [40, 70, 170],
+            Self::WarmOcean => [40, 110, 190],
+            Self::FrozenOcean | Self::FrozenRiver => [130, 150, 210],
+            Self::Beach => [220, 210, 150],
+            Self::SnowyBeach => [230, 230, 210],
+            Self::Desert => [230, 200, 110],
+            Self::Badlands | Self::ErodedBadlands | Self::WoodedBadlands => [190, 90, 40],
+            Self::Savanna | Self::SavannaPlateau | Self::WindsweptSavanna => [170, 160, 80],
+            Self::Plains | Self::Meadow | Self::SunflowerForest => [120, 180, 70],
+            Self::Forest | Self::FlowerForest => [60, 130, 50],
+            Self::BirchForest | Self::OldGrowthBirchForest => [90, 150, 70],
+            Self::DarkForest => [40, 80, 30],
+            Self::PaleGarden => [150, 160, 150],
+            Self::CherryGrove => [230, 160, 190],
+            Self::Jungle | Self::BambooJungle | Self::SparseJungle => [40, 150, 20],
+            Self::Swamp | Self::Mangrove => [70, 90, 50],
+            Self::Taiga | Self::OldGrowthPineTaiga | Self::OldGrowthSpruceTaiga => [50, 100, 80],
+            Self::SnowyTaiga | Self::Grove => [150, 180, 170],
+            Self::SnowyPlains | Self::SnowySlopes | Self::IceSpikes => [240, 240, 250],
+            Self::FrozenPeaks | Self::JaggedPeaks => [210, 220, 240],
+            Self::StonyPeaks | Self::StonyShore => [130, 130, 130],
+            Self::WindsweptHills | Self::WindsweptForest | Self::WindsweptGravellyHills => {
+                [100, 120, 100]
+            }
+        }
+    }
+
+    // horizon tint of the skybox, kept dark so the stars still show
+    pub const fn sky_color(&self) -> [f32; 3] {
+        match self {
+            Self::Desert
+            | Self::Badlands
+            | Self::ErodedBadlands
+            | Self::WoodedBadlands
+            | Self::Savanna
+            | Self::SavannaPlateau
+            | Self::WindsweptSavanna => [0.16, 0.11, 0.05],
+            Self::FrozenPeaks
+            | Self::JaggedPeaks
+            | Self::SnowySlopes
+            | Self::SnowyPlains
+            | Self::SnowyTaiga
+            | Self::SnowyBeach
+            | Self::IceSpikes
+            | Self::Grove
+            | Self::FrozenRiver
+            | Self::FrozenOcean
+            | Self::DeepFrozenOcean => [0.08, 0.11, 0.18],
+            Self::Taiga
+            | Self::OldGrowthPineTaiga
+            | Self::OldGrowthSpruceTaiga
+            | Self::WindsweptHills
+            | Self::WindsweptForest
+            | Self::WindsweptGravellyHills
+            | Self::StonyPeaks
+            | Self::StonyShore
+            | Self::ColdOcean
+            | Self::DeepColdOcean => [0.06, 0.08, 0.13],
+            Self::Swamp
+            | Self::Mangrove
+            | Self::Jungle
+            | Self::BambooJungle
+            | Self::SparseJungle => [0.06, 0.10, 0.06],
+            Self::Ocean | Self::WarmOcean | Self::DeepOcean | Self::River | Self::Beach => {
+                [0.04, 0.08, 0.14]
+            }
+            Self::CherryGrove | Self::FlowerForest | Self::SunflowerForest => [0.12, 0.07, 0.11],
+            Self::DarkForest | Self::PaleGarden => [0.06, 0.06, 0.06],
+            _ => [0.06, 0.09, 0.12],
+        }
+    }
+
+    // distant terrain fades into this color, usually the horizon tint
+    pub const fn fog_color(&self) -> [f32; 3] {
+        match self {
+            Self::Desert | Self::Badlands | Self::ErodedBadlands => [0.18, 0.14, 0.07],
+            Self::Swamp | Self::Mangrove => [0.05, 0.08, 0.04],
+            Self::DarkForest | Self::PaleGarden => [0.04, 0.04, 0.04],
+            _ => self.sky_color(),
+        }
+    }
+
+    // multiplies the water texture, the alpha replaces the usual transparency
+    pub const fn water_color(&self) -> [f32; 4] {
+        match self {
+            Self::FrozenOcean | Self::DeepFrozenOcean | Self::FrozenRiver => [0.6, 0.8, 0.95, 0.7],
+            Self::WarmOcean => [0.1, 0.7, 0.9, 0.7],
+            Self::Swamp | Self::Mangrove => [0.3, 0.4, 0.2, 0.8],
+            _ => [1.0, 1.0, 1.0, 0.6],
+        }
+    }
 }
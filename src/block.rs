@@ -1,12 +1,19 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use bincode::{Decode, Encode};
+
+pub const MAX_LIGHT: u8 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Encode, Decode)]
 #[repr(u8)]
 pub enum BlockType {
     Basalt,
+    CoalOre,
     Dirt,
     EmeraldOre,
     GoldOre,
     Grass,
     Ice,
+    IronOre,
+    Leaves,
     Magma,
     RedSand,
     RedStone,
@@ -15,17 +22,99 @@ pub enum BlockType {
     Stone,
     WarpedNylium,
     Water,
+    Wood,
 }
 
 impl BlockType {
+    pub const ALL: [Self; 18] = [
+        Self::Basalt,
+        Self::CoalOre,
+        Self::Dirt,
+        Self::EmeraldOre,
+        Self::GoldOre,
+        Self::Grass,
+        Self::Ice,
+        Self::IronOre,
+        Self::Leaves,
+        Self::Magma,
+        Self::RedSand,
+        Self::RedStone,
+        Self::Sand,
+        Self::Snow,
+        Self::Stone,
+        Self::WarpedNylium,
+        Self::Water,
+        Self::Wood,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Basalt => "Basalt",
+            Self::CoalOre => "Coal Ore",
+            Self::Dirt => "Dirt",
+            Self::EmeraldOre => "Emerald Ore",
+            Self::GoldOre => "Gold Ore",
+            Self::Grass => "Grass",
+            Self::Ice => "Ice",
+            Self::IronOre => "Iron Ore",
+            Self::Leaves => "Leaves",
+            Self::Magma => "Magma",
+            Self::RedSand => "Red Sand",
+            Self::RedStone => "Redstone Ore",
+            Self::Sand => "Sand",
+            Self::Snow => "Snow",
+            Self::Stone => "Stone",
+            Self::WarpedNylium => "Warped Nylium",
+            Self::Water => "Water",
+            Self::Wood => "Wood",
+        }
+    }
+
+    // case insensitive, underscores and dashes stand for spaces ("coal_ore")
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.replace(['_', '-'], " ");
+        Self::ALL
+            .into_iter()
+            .find(|block| block.name().eq_ignore_ascii_case(&name))
+    }
+
+    pub const fn is_transparent(&self) -> bool {
+        matches!(self, Self::Ice | Self::Leaves | Self::Water)
+    }
+
+    // blended when drawn, leaves are cut out along their texture in the opaque pass instead
+    pub const fn is_translucent(&self) -> bool {
+        matches!(self, Self::Ice | Self::Water)
+    }
+
+    // fluids can be walked through
+    pub const fn is_solid(&self) -> bool {
+        !self.is_fluid()
+    }
+
+    pub const fn is_fluid(&self) -> bool {
+        matches!(self, Self::Water)
+    }
+
+    // 0 to MAX_LIGHT, there is no propagation yet so only the block itself is lit
+    pub const fn light_emission(&self) -> u8 {
+        match self {
+            Self::Magma => 15,
+            _ => 0,
+        }
+    }
+
     pub const fn atlas_offset_top(&self) -> [u32; 2] {
         match self {
             Self::Basalt => [12, 6],
+            Self::CoalOre => [23, 9],
             Self::Dirt => [25, 2],
             Self::EmeraldOre => [23, 12],
             Self::GoldOre => [23, 13],
             Self::Grass => [31, 2],
             Self::Ice => [4, 22],
+            Self::IronOre => [23, 14],
+            Self::Leaves => [9, 4],
             Self::Magma => [24, 26],
             Self::RedSand => [29, 25],
             Self::RedStone => [24, 0],
@@ -34,17 +123,21 @@ impl BlockType {
             Self::Stone => [30, 29],
             Self::WarpedNylium => [33, 20],
             Self::Water => [6, 4],
+            Self::Wood => [31, 20],
         }
     }
 
     pub const fn atlas_offset_side(&self) -> [u32; 2] {
         match self {
             Self::Basalt => [12, 5],
+            Self::CoalOre => [23, 9],
             Self::Dirt => [25, 2],
             Self::EmeraldOre => [23, 12],
             Self::GoldOre => [23, 13],
             Self::Grass => [30, 15],
             Self::Ice => [4, 22],
+            Self::IronOre => [23, 14],
+            Self::Leaves => [9, 4],
             Self::Magma => [24, 26],
             Self::Sand => [6, 27],
             Self::Snow => [31, 1],
@@ -53,16 +146,20 @@ impl BlockType {
             Self::RedStone => [24, 0],
             Self::WarpedNylium => [33, 21],
             Self::Water => [6, 4],
+            Self::Wood => [30, 20],
         }
     }
 
     pub const fn atlas_offset_bottom(&self) -> [u32; 2] {
         match self {
             Self::Basalt => [12, 6],
+            Self::CoalOre => [23, 9],
             Self::Dirt | Self::Grass | Self::Snow => [25, 2],
             Self::EmeraldOre => [23, 12],
             Self::GoldOre => [23, 13],
             Self::Ice => [4, 22],
+            Self::IronOre => [23, 14],
+            Self::Leaves => [9, 4],
             Self::Magma => [24, 26],
             Self::RedSand => [27, 25],
             Self::RedStone => [24, 0],
@@ -70,6 +167,7 @@ impl BlockType {
             Self::Stone => [30, 29],
             Self::WarpedNylium => [25, 20],
             Self::Water => [6, 4],
+            Self::Wood => [31, 20],
         }
     }
 }
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+// small meshes share the same bucket instead of each getting its own size
+const MIN_BUCKET_SIZE: u64 = 1024;
+// past this, released buffers are dropped instead of staying in vram for the whole session
+const MAX_FREE_BYTES: u64 = 64 * 1024 * 1024;
+
+// recycles the chunk buffers instead of creating new ones every time a chunk is meshed
+#[derive(Default)]
+pub struct BufferPool {
+    free: HashMap<(wgpu::BufferUsages, u64), Vec<wgpu::Buffer>>,
+    free_bytes: u64,
+}
+
+impl BufferPool {
+    // the buffer is rounded up to the next power of two, the extra bytes are left as is
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        contents: &[u8],
+    ) -> wgpu::Buffer {
+        let usage = usage | wgpu::BufferUsages::COPY_DST;
+        let size = (contents.len() as u64)
+            .next_power_of_two()
+            .max(MIN_BUCKET_SIZE);
+        let buffer = self
+            .free
+            .get_mut(&(usage, size))
+            .and_then(Vec::pop)
+            .inspect(|buffer| self.free_bytes -= buffer.size())
+            .unwrap_or_else(|| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size,
+                    usage,
+                    mapped_at_creation: false,
+                })
+            });
+        queue.write_buffer(&buffer, 0, contents);
+        buffer
+    }
+
+    pub fn release(&mut self, buffer: wgpu::Buffer) {
+        if self.free_bytes + buffer.size() > MAX_FREE_BYTES {
+            return; // wgpu frees the buffer once the gpu is done with it
+        }
+        self.free_bytes += buffer.size();
+        self.free
+            .entry((buffer.usage(), buffer.size()))
+            .or_default()
+            .push(buffer);
+    }
+}
@@ -1,12 +1,14 @@
 use {
     crate::{
         Args,
+        aabb::AABB,
         chunk::{CHUNK_HEIGHT, CHUNK_WIDTH},
+        coords::{WorldCoords, camera_to_chunk_coords},
         frustum::Frustum,
-        state::RENDER_DISTANCE,
+        world::World,
     },
     glam::{Mat4, Vec3, Vec4},
-    std::f32::consts::{FRAC_PI_2, SQRT_2},
+    std::f32::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2},
     winit::{event::ElementState, keyboard::KeyCode},
 };
 
@@ -16,22 +18,77 @@ const MAX_PITCH: f32 = FRAC_PI_2 * 0.99; // avoids gimbal lock
 
 pub const CAMERA_NEAR: f32 = 0.1;
 
+const PLAYER_HALF_WIDTH: f32 = 0.3;
+const PLAYER_HEIGHT: f32 = 1.8;
+pub const PLAYER_EYE_HEIGHT: f32 = 1.62;
+const MAX_COLLISION_STEP: f32 = 0.5; // avoids tunneling through blocks at high speed
+
+const SENSITIVITY_STEP: f32 = 0.0005;
+
+const FOV_STEP: f32 = 5.0; // degrees per scroll line
+const MIN_FOV_Y: f32 = 10.0;
+const MAX_FOV_Y: f32 = 120.0;
+const ZOOM_SMOOTHING: f32 = 12.0; // higher is snappier
+
+const GRAVITY: f32 = -9.8;
+const JUMP_VELOCITY: f32 = 5.0;
+const TERMINAL_VELOCITY: f32 = -50.0;
+const GROUND_EPSILON: f32 = 0.01;
+const VELOCITY_EPSILON: f32 = 0.001;
+
 // not const because of f32::sqrt :(
-pub fn camera_far() -> f32 {
-    let camera_far_xy = (RENDER_DISTANCE + 1.0) * SQRT_2 * CHUNK_WIDTH as f32;
+pub fn camera_far(render_distance: f32) -> f32 {
+    let camera_far_xy = (render_distance + 1.0) * SQRT_2 * CHUNK_WIDTH as f32;
     f32::hypot(camera_far_xy, CHUNK_HEIGHT as f32)
 }
 
+pub fn player_aabb(eye: Vec3) -> AABB {
+    AABB::new(
+        eye - Vec3::new(PLAYER_HALF_WIDTH, PLAYER_HALF_WIDTH, PLAYER_EYE_HEIGHT),
+        eye + Vec3::new(
+            PLAYER_HALF_WIDTH,
+            PLAYER_HALF_WIDTH,
+            PLAYER_HEIGHT - PLAYER_EYE_HEIGHT,
+        ),
+    )
+}
+
+fn collides(world: &World, eye: Vec3) -> bool {
+    let player = player_aabb(eye);
+    let min = player.min.floor().as_ivec3();
+    let max = player.max.floor().as_ivec3();
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                if !world
+                    .get_block(WorldCoords::new(x, y, z))
+                    .is_some_and(|block| block.is_solid())
+                {
+                    continue;
+                }
+                let block_min = Vec3::new(x as f32, y as f32, z as f32);
+                if player.intersects_aabb(&AABB::new(block_min, block_min + Vec3::ONE)) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
     view_proj: [[f32; 4]; 4],
     view_proj_skybox_inverse: [[f32; 4]; 4],
     pos: [f32; 3],
-    _pad0: f32,
+    focus_distance: f32,
+    near: f32,
+    far: f32,
+    _pad0: [f32; 2],
 }
 impl CameraUniform {
-    pub fn new(camera: &Camera) -> Self {
+    pub fn new(camera: &Camera, focus_distance: f32) -> Self {
         let view = camera.look_at();
         let view_skybox = camera.look_at_skybox();
         let proj = camera.projection();
@@ -44,7 +101,10 @@ impl CameraUniform {
             view_proj: view_proj.to_cols_array_2d(),
             view_proj_skybox_inverse: view_proj_skybox_inverse.to_cols_array_2d(),
             pos: camera.position().to_array(),
-            _pad0: 0.0,
+            focus_distance,
+            near: camera.near,
+            far: camera.far,
+            _pad0: [0.0; 2],
         }
     }
 }
@@ -55,6 +115,8 @@ pub struct Camera {
     yaw: f32,
     pitch: f32,
     aspect: f32,
+    // the horizontal fov is kept when the aspect ratio changes
+    fov_x: f32,
     fov_y: f32,
     near: f32,
     far: f32,
@@ -62,20 +124,20 @@ pub struct Camera {
 }
 impl Camera {
     pub fn new(eye: Vec3, up: Vec3, aspect: f32, fov_x: f32, near: f32, far: f32) -> Self {
-        let fov_y = 2.0 * (fov_x / 2.0).tan().atan2(aspect);
-        let projection = Mat4::perspective_rh(fov_y, aspect, near, far);
-
-        Self {
+        let mut camera = Self {
             eye,
             up,
             aspect,
             yaw: 0.0,
             pitch: -0.2,
-            fov_y,
+            fov_x,
+            fov_y: fov_y_from_x(fov_x, aspect),
             near,
             far,
-            projection,
-        }
+            projection: Mat4::IDENTITY,
+        };
+        camera.rebuild_projection();
+        camera
     }
 
     pub fn look_at(&self) -> Mat4 {
@@ -109,26 +171,97 @@ impl Camera {
         .normalize()
     }
 
+    pub const fn far(&self) -> f32 {
+        self.far
+    }
+
     pub const fn position(&self) -> Vec3 {
         self.eye
     }
 
+    // north is +y and east is +x, like the adjacent chunks
+    pub fn facing_cardinal(&self) -> &'static str {
+        const CARDINALS: [&str; 8] = [
+            "North",
+            "Northeast",
+            "East",
+            "Southeast",
+            "South",
+            "Southwest",
+            "West",
+            "Northwest",
+        ];
+        let direction = self.direction();
+        let angle = direction.x.atan2(direction.y);
+        let sector = (angle / FRAC_PI_4).round() as i32;
+        CARDINALS[sector.rem_euclid(8) as usize]
+    }
+
+    pub const fn fov_x_deg(&self) -> f32 {
+        self.fov_x.to_degrees()
+    }
+
+    pub const fn fov_y_deg(&self) -> f32 {
+        self.fov_y.to_degrees()
+    }
+
+    fn set_fov_y(&mut self, fov_y: f32) {
+        self.fov_y = fov_y;
+        self.fov_x = fov_x_from_y(fov_y, self.aspect);
+        self.rebuild_projection();
+    }
+
+    pub const fn altitude(&self) -> f32 {
+        self.eye.z
+    }
+
     pub fn get_frustum(&self) -> Frustum {
         let view_proj = self.projection() * self.look_at();
         Frustum::from_matrix(view_proj)
     }
 
+    // origin on the near plane and normalized direction of the ray through a point of the screen
+    pub fn get_pick_ray(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        let inverse_view_proj = (self.projection() * self.look_at()).inverse();
+        let near = inverse_view_proj.project_point3(Vec3::new(ndc_x, ndc_y, 0.0));
+        let far = inverse_view_proj.project_point3(Vec3::new(ndc_x, ndc_y, 1.0));
+        (near, (far - near).normalize())
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         self.aspect = width as f32 / height as f32;
+        self.fov_y = fov_y_from_x(self.fov_x, self.aspect);
+        self.rebuild_projection();
+    }
+
+    pub fn set_far(&mut self, far: f32) {
+        self.far = far;
+        self.rebuild_projection();
+    }
+
+    pub fn rebuild_projection(&mut self) {
         self.projection = Mat4::perspective_rh(self.fov_y, self.aspect, self.near, self.far);
     }
 }
 
+fn fov_y_from_x(fov_x: f32, aspect: f32) -> f32 {
+    2.0 * (fov_x / 2.0).tan().atan2(aspect)
+}
+
+fn fov_x_from_y(fov_y: f32, aspect: f32) -> f32 {
+    2.0 * ((fov_y / 2.0).tan() * aspect).atan()
+}
+
 pub struct CameraController {
     normal_speed: f32,
     boosted_speed: f32,
-    sensitivity: f32,
+    pub sensitivity: f32,
+    inertia: f32, // higher values reach the target velocity faster
+    velocity: Vec3,
     vertical_enabled: bool,
+    is_flying: bool,
+    vertical_velocity: f32,
+    is_on_ground: bool,
     is_boosted: bool,
     is_forward_pressed: bool,
     is_backward_pressed: bool,
@@ -137,14 +270,20 @@ pub struct CameraController {
     is_up_pressed: bool,
     is_down_pressed: bool,
     mouse_delta: (f32, f32),
+    zoom_delta: f32, // radians of fov_y still to apply
 }
 impl CameraController {
     pub const fn new(args: &Args) -> Self {
         Self {
             normal_speed: args.normal_speed,
             boosted_speed: args.boosted_speed,
-            sensitivity: 0.004,
+            sensitivity: args.sensitivity,
+            inertia: args.inertia,
+            velocity: Vec3::ZERO,
             vertical_enabled: args.vertical_enabled,
+            is_flying: true,
+            vertical_velocity: 0.0,
+            is_on_ground: false,
             is_boosted: false,
             is_forward_pressed: false,
             is_backward_pressed: false,
@@ -153,6 +292,7 @@ impl CameraController {
             is_up_pressed: false,
             is_down_pressed: false,
             mouse_delta: (0.0, 0.0),
+            zoom_delta: 0.0,
         }
     }
 
@@ -161,6 +301,19 @@ impl CameraController {
         self.mouse_delta.1 += delta_y;
     }
 
+    pub fn increase_sensitivity(&mut self) {
+        self.sensitivity += SENSITIVITY_STEP;
+    }
+
+    pub fn decrease_sensitivity(&mut self) {
+        self.sensitivity = (self.sensitivity - SENSITIVITY_STEP).max(SENSITIVITY_STEP);
+    }
+
+    pub fn process_scroll(&mut self, delta: f32) {
+        // scrolling up zooms in
+        self.zoom_delta -= (delta * FOV_STEP).to_radians();
+    }
+
     pub const fn process_boost(&mut self, is_pressed: bool) {
         self.is_boosted = is_pressed;
     }
@@ -180,7 +333,7 @@ impl CameraController {
             KeyCode::KeyD | KeyCode::ArrowRight => {
                 self.is_right_pressed = is_pressed;
             }
-            KeyCode::Space if self.vertical_enabled => {
+            KeyCode::Space if self.vertical_enabled || !self.is_flying => {
                 self.is_up_pressed = is_pressed;
             }
             KeyCode::ShiftLeft | KeyCode::ControlLeft if self.vertical_enabled => {
@@ -190,30 +343,112 @@ impl CameraController {
         }
     }
 
-    pub fn update(&mut self, camera: &mut Camera, dt: f32) {
+    pub const fn toggle_flying(&mut self) {
+        self.is_flying = !self.is_flying;
+        self.vertical_velocity = 0.0;
+        self.is_up_pressed = false;
+        self.is_down_pressed = false;
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, world: &World, dt: f32) {
         // === ROTATION ===
         let (dx, dy) = self.mouse_delta;
         camera.yaw += dx * self.sensitivity;
         camera.pitch = (camera.pitch - dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
         self.mouse_delta = (0.0, 0.0);
 
+        // === ZOOM ===
+        if self.zoom_delta != 0.0 {
+            let applied = self.zoom_delta * (1.0 - (-ZOOM_SMOOTHING * dt).exp());
+            let fov_range = MIN_FOV_Y.to_radians()..=MAX_FOV_Y.to_radians();
+            let fov_y = camera.fov_y + applied;
+            camera.set_fov_y(fov_y.clamp(*fov_range.start(), *fov_range.end()));
+            self.zoom_delta -= applied;
+            // stop at the bounds instead of pushing against them
+            if !fov_range.contains(&fov_y) || self.zoom_delta.abs() < f32::EPSILON {
+                self.zoom_delta = 0.0;
+            }
+        }
+
         // === MOVEMENT ===
-        let forward = camera.direction();
-        let right = forward.cross(camera.up);
+        // without vertical movement, flying keeps the eye at the same height
+        let forward = if self.is_flying && self.vertical_enabled {
+            camera.direction()
+        } else {
+            camera.direction().with_z(0.0).normalize_or_zero()
+        };
+        let right = forward.cross(camera.up).normalize_or_zero();
 
         let mut movement = Vec3::ZERO;
         movement += forward * (self.is_forward_pressed as i32) as f32;
         movement -= forward * (self.is_backward_pressed as i32) as f32;
         movement += right * (self.is_right_pressed as i32) as f32;
         movement -= right * (self.is_left_pressed as i32) as f32;
-        movement += camera.up * (self.is_up_pressed as i32) as f32;
-        movement -= camera.up * (self.is_down_pressed as i32) as f32;
+        if self.is_flying {
+            movement += camera.up * (self.is_up_pressed as i32) as f32;
+            movement -= camera.up * (self.is_down_pressed as i32) as f32;
+        }
 
-        if movement == Vec3::ZERO {
+        let desired_velocity = movement.normalize_or_zero() * self.speed();
+        self.velocity = self
+            .velocity
+            .lerp(desired_velocity, 1.0 - (-self.inertia * dt).exp());
+        // the lerp never quite gets there, which would keep the player drifting
+        if self
+            .velocity
+            .abs_diff_eq(desired_velocity, VELOCITY_EPSILON)
+        {
+            self.velocity = desired_velocity;
+        }
+        let mut delta = self.velocity * dt;
+        // lets the player out when a block ends up inside them
+        let is_stuck = collides(world, camera.eye);
+
+        // === GRAVITY ===
+        if !self.is_flying {
+            // don't fall through chunks that haven't been generated yet
+            if is_stuck
+                || world
+                    .get_chunk_if_loaded(camera_to_chunk_coords(camera.eye))
+                    .is_none()
+            {
+                self.vertical_velocity = 0.0;
+            } else {
+                if self.is_on_ground && self.is_up_pressed {
+                    self.vertical_velocity = JUMP_VELOCITY;
+                }
+                self.vertical_velocity =
+                    (self.vertical_velocity + GRAVITY * dt).max(TERMINAL_VELOCITY);
+            }
+            delta.z += self.vertical_velocity * dt;
+        }
+
+        if delta == Vec3::ZERO {
             return;
         }
 
-        camera.eye += movement.normalize() * self.speed() * dt;
+        let steps = (delta.abs().max_element() / MAX_COLLISION_STEP)
+            .ceil()
+            .max(1.0);
+        let step = delta / steps;
+        for _ in 0..steps as u32 {
+            // moving one axis at a time makes the player slide along walls
+            for axis in 0..3 {
+                let mut eye = camera.eye;
+                eye[axis] += step[axis];
+                if is_stuck || !collides(world, eye) {
+                    camera.eye = eye;
+                } else {
+                    // don't keep pushing against the wall
+                    self.velocity[axis] = 0.0;
+                    if axis == 2 {
+                        // landed or bumped the ceiling
+                        self.vertical_velocity = 0.0;
+                    }
+                }
+            }
+        }
+        self.is_on_ground = collides(world, camera.eye - Vec3::Z * GROUND_EPSILON);
         camera.eye.z = camera.eye.z.clamp(
             -CAMERA_MAX_OUT_OF_BOUNDS,
             CHUNK_HEIGHT as f32 + CAMERA_MAX_OUT_OF_BOUNDS,
@@ -1,7 +1,7 @@
 use {
     crate::{
         aabb::AABB,
-        block::BlockType,
+        block::{BlockType, MAX_LIGHT},
         coords::{BlockCoords, ChunkCoords},
         face::Face,
         vertex::Vertex,
@@ -12,13 +12,62 @@ use {
 pub const CHUNK_WIDTH: usize = 16;
 pub const CHUNK_HEIGHT: usize = 256;
 
-type Blocks = [[[Option<BlockType>; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH];
+const CHUNK_DIMS: [usize; 3] = [CHUNK_WIDTH, CHUNK_WIDTH, CHUNK_HEIGHT];
+
+// a generated chunk has about 1600 leaves, so about twice as many nodes
+const AVERAGE_LEAF_COUNT: usize = 1600;
+pub const CHUNK_MEMORY_ESTIMATE: usize =
+    size_of::<Chunk>() + 2 * AVERAGE_LEAF_COUNT * size_of::<ChunkNode>();
+
+pub type Blocks = [[[Option<BlockType>; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH];
+type GreedyMask = Vec<Option<(BlockType, [f32; 4])>>;
+
+#[derive(Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+impl Mesh {
+    pub const fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn push_face(&mut self, face: Face, block: BlockType, pos: ChunkNodePos, ao: [f32; 4]) {
+        let index_offset = self.vertices.len() as u32;
+        self.vertices
+            .extend(create_face_vertices(face, block, pos, ao));
+        self.indices.extend(quad_indices(index_offset, ao));
+    }
+}
+
+#[derive(Default)]
+pub struct ChunkMesh {
+    pub opaque: Mesh,
+    pub transparent: Mesh,
+    pub water: Mesh, // tinted by the biome of the camera
+}
+impl ChunkMesh {
+    fn push_face(&mut self, face: Face, block: BlockType, pos: ChunkNodePos, ao: [f32; 4]) {
+        let mesh = if block == BlockType::Water {
+            &mut self.water
+        } else if block.is_translucent() {
+            &mut self.transparent
+        } else {
+            &mut self.opaque
+        };
+        mesh.push_face(face, block, pos, ao);
+    }
+}
 
 pub struct AdjacentChunks<'a> {
     pub north: Option<&'a Chunk>,
     pub south: Option<&'a Chunk>,
     pub east: Option<&'a Chunk>,
     pub west: Option<&'a Chunk>,
+    pub northeast: Option<&'a Chunk>,
+    pub northwest: Option<&'a Chunk>,
+    pub southeast: Option<&'a Chunk>,
+    pub southwest: Option<&'a Chunk>,
 }
 
 pub struct Chunk {
@@ -32,171 +81,337 @@ impl Chunk {
             &blocks,
             ChunkNodePos::new(0, CHUNK_WIDTH, 0, CHUNK_WIDTH, 0, CHUNK_HEIGHT),
         );
+        let chunk = Self { coords, root };
         log::debug!(
-            "Chunk {:?} : {}/{} leaves",
+            "Chunk {:?} : {}/{} leaves, {} blocks",
             coords,
-            root.count_leaves(),
-            CHUNK_WIDTH * CHUNK_WIDTH * CHUNK_HEIGHT
+            chunk.root.count_leaves(),
+            CHUNK_WIDTH * CHUNK_WIDTH * CHUNK_HEIGHT,
+            chunk.block_count(),
         );
-        Self { coords, root }
+        chunk
     }
 
-    pub fn get_block(&self, (x, y, z): BlockCoords) -> Option<BlockType> {
-        debug_assert!(x < CHUNK_WIDTH);
-        debug_assert!(y < CHUNK_WIDTH);
-        debug_assert!(z < CHUNK_HEIGHT);
-        self.root.get_at(x, y, z, 0, 0, 0)
+    pub fn get_block(&self, BlockCoords { bx, by, bz }: BlockCoords) -> Option<BlockType> {
+        debug_assert!(bx < CHUNK_WIDTH);
+        debug_assert!(by < CHUNK_WIDTH);
+        debug_assert!(bz < CHUNK_HEIGHT);
+        self.root.get_at(bx, by, bz, 0, 0, 0)
     }
 
     fn get_blocks(&self) -> Blocks {
         let mut blocks = [[[None; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH];
-        self.root.fill_blocks(&mut blocks);
+        for (x, y, z, block) in self.iter_blocks() {
+            blocks[x][y][z] = block;
+        }
         blocks
     }
 
+    // walks the leaves instead of descending the tree once per block
+    pub fn iter_blocks(&self) -> impl Iterator<Item = (usize, usize, usize, Option<BlockType>)> {
+        let mut leaves = Vec::new();
+        self.root.collect_leaves(&mut leaves);
+        leaves.into_iter().flat_map(|(block, pos)| {
+            (pos.x0..pos.x1).flat_map(move |x| {
+                (pos.y0..pos.y1).flat_map(move |y| (pos.z0..pos.z1).map(move |z| (x, y, z, block)))
+            })
+        })
+    }
+
+    // min and max are inclusive, only the branches intersecting the region are visited
+    #[expect(dead_code)] // TODO: ore visibility, tree canopies and pathfinding
+    pub fn any_block_of_type(&self, min: BlockCoords, max: BlockCoords, block: BlockType) -> bool {
+        let region = ChunkNodePos::new(min.bx, max.bx + 1, min.by, max.by + 1, min.bz, max.bz + 1);
+        self.root.any_block_of_type(region, block)
+    }
+
+    // non-empty blocks, without visiting the uniform regions block by block
+    pub const fn block_count(&self) -> u32 {
+        self.root.count_solid()
+    }
+
     // TODO: optimize
-    pub fn delete_block(&mut self, (x, y, z): BlockCoords) {
-        debug_assert!(x < CHUNK_WIDTH);
-        debug_assert!(y < CHUNK_WIDTH);
-        debug_assert!(z < CHUNK_HEIGHT);
+    pub fn delete_block(&mut self, block_coords: BlockCoords) {
+        self.set_blocks(&[(block_coords, None)]);
+    }
+
+    pub fn place_block(&mut self, block_coords: BlockCoords, block: BlockType) {
+        self.set_blocks(&[(block_coords, Some(block))]);
+    }
+
+    // rebuilds the tree once for the whole batch
+    pub fn set_blocks(&mut self, changes: &[(BlockCoords, Option<BlockType>)]) {
         let mut blocks = self.get_blocks();
-        blocks[x][y][z] = None;
+        for &(BlockCoords { bx, by, bz }, block) in changes {
+            debug_assert!(bx < CHUNK_WIDTH);
+            debug_assert!(by < CHUNK_WIDTH);
+            debug_assert!(bz < CHUNK_HEIGHT);
+            blocks[bx][by][bz] = block;
+        }
         self.root = ChunkNode::from_region(
             &blocks,
             ChunkNodePos::new(0, CHUNK_WIDTH, 0, CHUNK_WIDTH, 0, CHUNK_HEIGHT),
         );
     }
 
+    // only covers the non-empty blocks, so mostly empty chunks cull more easily
     pub fn bounding_box(&self) -> AABB {
-        let (x, y) = self.coords;
-        let world_x = x as f32 * CHUNK_WIDTH as f32;
-        let world_y = y as f32 * CHUNK_WIDTH as f32;
-
-        AABB::new(
-            Vec3::new(world_x, world_y, 0.0),
-            Vec3::new(
-                world_x + CHUNK_WIDTH as f32,
-                world_y + CHUNK_WIDTH as f32,
-                CHUNK_HEIGHT as f32,
-            ),
+        let ChunkCoords { cx, cy } = self.coords;
+        let offset = Vec3::new(
+            cx as f32 * CHUNK_WIDTH as f32,
+            cy as f32 * CHUNK_WIDTH as f32,
+            0.0,
+        );
+
+        self.root.tight_aabb().map_or_else(
+            || {
+                AABB::new(
+                    offset,
+                    offset + Vec3::new(CHUNK_WIDTH as f32, CHUNK_WIDTH as f32, CHUNK_HEIGHT as f32),
+                )
+            },
+            |aabb| AABB::new(aabb.min + offset, aabb.max + offset),
         )
     }
 
-    pub fn generate_mesh(&self, adjacent: &AdjacentChunks) -> (Vec<Vertex>, Vec<u16>) {
-        let (vertices, indices, _) = self.root.generate_mesh(self, adjacent);
-        (vertices, indices)
+    pub fn generate_mesh(&self, adjacent: &AdjacentChunks) -> ChunkMesh {
+        let mut mesh = ChunkMesh::default();
+        self.root.generate_mesh(self, adjacent, &mut mesh);
+        mesh
     }
 
-    #[expect(clippy::too_many_lines)]
-    fn is_face_visible(&self, pos: &ChunkNodePos, face: Face, adjacent: &AdjacentChunks) -> bool {
+    // sweeps every slice along each face normal and merges visible faces sharing
+    // the same block type and ambient occlusion into the largest possible rectangles
+    pub fn generate_greedy_mesh(&self, adjacent: &AdjacentChunks) -> ChunkMesh {
+        let blocks = self.get_blocks();
+        let mut mesh = ChunkMesh::default();
+
+        for face in Face::ALL {
+            let (normal_axis, _, _) = slice_axes(face);
+            for slice in 0..CHUNK_DIMS[normal_axis] {
+                let mut mask = self.greedy_mask(&blocks, face, slice, adjacent);
+                for (block, pos, ao) in greedy_merge(&mut mask, face, slice) {
+                    mesh.push_face(face, block, pos, ao);
+                }
+            }
+        }
+
+        mesh
+    }
+
+    fn greedy_mask(
+        &self,
+        blocks: &Blocks,
+        face: Face,
+        slice: usize,
+        adjacent: &AdjacentChunks,
+    ) -> GreedyMask {
+        let (normal_axis, u_axis, v_axis) = slice_axes(face);
+        let (u_size, v_size) = (CHUNK_DIMS[u_axis], CHUNK_DIMS[v_axis]);
+        let mut mask = vec![None; u_size * v_size];
+
+        for v in 0..v_size {
+            for u in 0..u_size {
+                let [x, y, z] = slice_cell(face, slice, u, v);
+                let Some(block) = blocks[x][y][z] else {
+                    continue;
+                };
+
+                let mut neighbor = [x as i32, y as i32, z as i32];
+                neighbor[normal_axis] += face.normal()[normal_axis] as i32;
+                let visible = match face {
+                    Face::Bottom => z > 0 && shows_face(block, self.block_at(neighbor, adjacent)),
+                    _ => shows_face(block, self.block_at(neighbor, adjacent)),
+                };
+                if visible {
+                    let pos = ChunkNodePos::new(x, x + 1, y, y + 1, z, z + 1);
+                    mask[v * u_size + u] = Some((block, self.face_ao(pos, face, adjacent)));
+                }
+            }
+        }
+
+        mask
+    }
+
+    // water, ice and leaves let the light through and don't darken their neighbors
+    fn is_solid_at(&self, cell: [i32; 3], adjacent: &AdjacentChunks) -> bool {
+        self.block_at(cell, adjacent)
+            .is_some_and(|block| block.is_solid() && !block.is_transparent())
+    }
+
+    // blocks outside of this chunk are looked up in the adjacent chunks (empty if missing)
+    fn block_at(&self, [x, y, z]: [i32; 3], adjacent: &AdjacentChunks) -> Option<BlockType> {
+        if z < 0 || z >= CHUNK_HEIGHT as i32 {
+            return None;
+        }
+
+        let width = CHUNK_WIDTH as i32;
+        let chunk = match (x.div_euclid(width), y.div_euclid(width)) {
+            (0, 0) => Some(self),
+            (0, 1) => adjacent.north,
+            (0, -1) => adjacent.south,
+            (1, 0) => adjacent.east,
+            (-1, 0) => adjacent.west,
+            (1, 1) => adjacent.northeast,
+            (-1, 1) => adjacent.northwest,
+            (1, -1) => adjacent.southeast,
+            (-1, -1) => adjacent.southwest,
+            _ => None,
+        };
+
+        chunk?.get_block(BlockCoords::new(
+            x.rem_euclid(width) as usize,
+            y.rem_euclid(width) as usize,
+            z as usize,
+        ))
+    }
+
+    // for each corner of the face, count the solid blocks among the two edge neighbors
+    // and the diagonal neighbor in the layer just outside the face
+    fn face_ao(&self, pos: ChunkNodePos, face: Face, adjacent: &AdjacentChunks) -> [f32; 4] {
+        let normal = face.normal();
+        let (normal_axis, u_axis, v_axis) = slice_axes(face);
+        let tangent_axes = [u_axis, v_axis];
+        let min = [pos.x0 as i32, pos.y0 as i32, pos.z0 as i32];
+        let max = [pos.x1 as i32, pos.y1 as i32, pos.z1 as i32];
+
+        face.positions().map(|corner| {
+            let mut cell = [0; 3];
+            let mut step = [0; 3];
+            cell[normal_axis] = if normal[normal_axis] > 0.0 {
+                max[normal_axis]
+            } else {
+                min[normal_axis] - 1
+            };
+            for axis in tangent_axes {
+                (cell[axis], step[axis]) = if corner[axis] == 0.0 {
+                    (min[axis], -1)
+                } else {
+                    (max[axis] - 1, 1)
+                };
+            }
+
+            let [a, b] = tangent_axes;
+            let mut side_a = cell;
+            side_a[a] += step[a];
+            let mut side_b = cell;
+            side_b[b] += step[b];
+            let mut diagonal = side_a;
+            diagonal[b] += step[b];
+
+            let occluders = [side_a, side_b, diagonal]
+                .into_iter()
+                .filter(|&neighbor| self.is_solid_at(neighbor, adjacent))
+                .count();
+            1.0 - 0.25 * occluders as f32
+        })
+    }
+
+    fn is_face_visible(
+        &self,
+        pos: ChunkNodePos,
+        face: Face,
+        block: BlockType,
+        adjacent: &AdjacentChunks,
+    ) -> bool {
         // build the 1-voxel-thick neighbor "slab" touching `pos` on `face`.
         // if the slab is inside this chunk, query `self`. If it lies outside, query the
         // corresponding adjacent chunk (or treat as empty if missing).
+        // opaque blocks can also be seen through transparent neighbors, while transparent blocks
+        // (and fluids in particular) hide the faces they share, so water-to-water faces are culled.
+        let see_through = |chunk: &Self, region: ChunkNodePos| {
+            chunk.root.any_empty_in_region(region)
+                || !block.is_transparent() && chunk.root.any_transparent_in_region(region)
+        };
 
         match face {
             Face::Left => {
                 if pos.x0 > 0 {
-                    self.root.any_empty_in_region(&ChunkNodePos::new(
-                        pos.x0 - 1,
-                        pos.x0,
-                        pos.y0,
-                        pos.y1,
-                        pos.z0,
-                        pos.z1,
-                    ))
+                    see_through(
+                        self,
+                        ChunkNodePos::new(pos.x0 - 1, pos.x0, pos.y0, pos.y1, pos.z0, pos.z1),
+                    )
                 } else {
                     adjacent.west.is_none_or(|west| {
-                        west.root.any_empty_in_region(&ChunkNodePos::new(
-                            CHUNK_WIDTH - 1,
-                            CHUNK_WIDTH,
-                            pos.y0,
-                            pos.y1,
-                            pos.z0,
-                            pos.z1,
-                        ))
+                        see_through(
+                            west,
+                            ChunkNodePos::new(
+                                CHUNK_WIDTH - 1,
+                                CHUNK_WIDTH,
+                                pos.y0,
+                                pos.y1,
+                                pos.z0,
+                                pos.z1,
+                            ),
+                        )
                     })
                 }
             }
             Face::Right => {
                 if pos.x1 < CHUNK_WIDTH {
-                    self.root.any_empty_in_region(&ChunkNodePos::new(
-                        pos.x1,
-                        pos.x1 + 1,
-                        pos.y0,
-                        pos.y1,
-                        pos.z0,
-                        pos.z1,
-                    ))
+                    see_through(
+                        self,
+                        ChunkNodePos::new(pos.x1, pos.x1 + 1, pos.y0, pos.y1, pos.z0, pos.z1),
+                    )
                 } else {
                     adjacent.east.is_none_or(|east| {
-                        east.root.any_empty_in_region(&ChunkNodePos::new(
-                            0, 1, pos.y0, pos.y1, pos.z0, pos.z1,
-                        ))
+                        see_through(
+                            east,
+                            ChunkNodePos::new(0, 1, pos.y0, pos.y1, pos.z0, pos.z1),
+                        )
                     })
                 }
             }
             Face::Back => {
                 if pos.y1 < CHUNK_WIDTH {
-                    self.root.any_empty_in_region(&ChunkNodePos::new(
-                        pos.x0,
-                        pos.x1,
-                        pos.y1,
-                        pos.y1 + 1,
-                        pos.z0,
-                        pos.z1,
-                    ))
+                    see_through(
+                        self,
+                        ChunkNodePos::new(pos.x0, pos.x1, pos.y1, pos.y1 + 1, pos.z0, pos.z1),
+                    )
                 } else {
                     adjacent.north.is_none_or(|north| {
-                        north.root.any_empty_in_region(&ChunkNodePos::new(
-                            pos.x0, pos.x1, 0, 1, pos.z0, pos.z1,
-                        ))
+                        see_through(
+                            north,
+                            ChunkNodePos::new(pos.x0, pos.x1, 0, 1, pos.z0, pos.z1),
+                        )
                     })
                 }
             }
             Face::Front => {
                 if pos.y0 > 0 {
-                    self.root.any_empty_in_region(&ChunkNodePos::new(
-                        pos.x0,
-                        pos.x1,
-                        pos.y0 - 1,
-                        pos.y0,
-                        pos.z0,
-                        pos.z1,
-                    ))
+                    see_through(
+                        self,
+                        ChunkNodePos::new(pos.x0, pos.x1, pos.y0 - 1, pos.y0, pos.z0, pos.z1),
+                    )
                 } else {
                     adjacent.south.is_none_or(|south| {
-                        south.root.any_empty_in_region(&ChunkNodePos::new(
-                            pos.x0,
-                            pos.x1,
-                            CHUNK_WIDTH - 1,
-                            CHUNK_WIDTH,
-                            pos.z0,
-                            pos.z1,
-                        ))
+                        see_through(
+                            south,
+                            ChunkNodePos::new(
+                                pos.x0,
+                                pos.x1,
+                                CHUNK_WIDTH - 1,
+                                CHUNK_WIDTH,
+                                pos.z0,
+                                pos.z1,
+                            ),
+                        )
                     })
                 }
             }
             Face::Top => {
                 pos.z1 >= CHUNK_HEIGHT
-                    || self.root.any_empty_in_region(&ChunkNodePos::new(
-                        pos.x0,
-                        pos.x1,
-                        pos.y0,
-                        pos.y1,
-                        pos.z1,
-                        pos.z1 + 1,
-                    ))
+                    || see_through(
+                        self,
+                        ChunkNodePos::new(pos.x0, pos.x1, pos.y0, pos.y1, pos.z1, pos.z1 + 1),
+                    )
             }
             Face::Bottom => {
                 pos.z0 > 0 && {
-                    self.root.any_empty_in_region(&ChunkNodePos::new(
-                        pos.x0,
-                        pos.x1,
-                        pos.y0,
-                        pos.y1,
-                        pos.z0 - 1,
-                        pos.z0,
-                    ))
+                    see_through(
+                        self,
+                        ChunkNodePos::new(pos.x0, pos.x1, pos.y0, pos.y1, pos.z0 - 1, pos.z0),
+                    )
                 }
             }
         }
@@ -212,45 +427,24 @@ enum SplitDir {
 
 enum ChunkNode {
     Leaf(Option<BlockType>, ChunkNodePos),
-    Inner(Box<Self>, Box<Self>, SplitDir, ChunkNodePos),
+    // the number of non-empty blocks below is cached, the tree is rebuilt on every change anyway
+    Inner(Box<Self>, Box<Self>, SplitDir, ChunkNodePos, u32),
 }
 impl ChunkNode {
-    fn generate_mesh(
-        &self,
-        chunk: &Chunk,
-        adjacent: &AdjacentChunks,
-    ) -> (Vec<Vertex>, Vec<u16>, u16) {
+    fn generate_mesh(&self, chunk: &Chunk, adjacent: &AdjacentChunks, mesh: &mut ChunkMesh) {
         match self {
-            Self::Leaf(None, _) => (vec![], vec![], 0),
-            Self::Leaf(Some(block_type), pos) => {
-                let mut vertices = Vec::new();
-                let mut indices = Vec::new();
-                let mut index_offset = 0;
-
+            Self::Leaf(None, _) => {}
+            &Self::Leaf(Some(block_type), pos) => {
                 for face in Face::ALL {
-                    if chunk.is_face_visible(pos, face, adjacent) {
-                        vertices.extend(create_face_vertices(face, *block_type, pos));
-                        indices.extend([
-                            index_offset,
-                            index_offset + 1,
-                            index_offset + 2,
-                            index_offset + 2,
-                            index_offset + 3,
-                            index_offset,
-                        ]);
-                        index_offset += 4;
+                    if chunk.is_face_visible(pos, face, block_type, adjacent) {
+                        let ao = chunk.face_ao(pos, face, adjacent);
+                        mesh.push_face(face, block_type, pos, ao);
                     }
                 }
-
-                (vertices, indices, index_offset)
             }
-            Self::Inner(a, b, _, _) => {
-                let (mut vertices_a, mut indices_a, index_offset_a) =
-                    a.generate_mesh(chunk, adjacent);
-                let (vertices_b, indices_b, index_offset_b) = b.generate_mesh(chunk, adjacent);
-                vertices_a.extend(vertices_b);
-                indices_a.extend(indices_b.iter().map(|i| i + index_offset_a));
-                (vertices_a, indices_a, index_offset_a + index_offset_b)
+            Self::Inner(a, b, ..) => {
+                a.generate_mesh(chunk, adjacent, mesh);
+                b.generate_mesh(chunk, adjacent, mesh);
             }
         }
     }
@@ -259,7 +453,7 @@ impl ChunkNode {
         let (sx, sy, sz) = pos.size();
         debug_assert!(sx > 0 && sy > 0 && sz > 0);
 
-        if let Some(u) = uniform(blocks, &pos) {
+        if let Some(u) = uniform(blocks, pos) {
             return Self::Leaf(u, pos);
         }
 
@@ -282,6 +476,20 @@ impl ChunkNode {
         }
     }
 
+    fn tight_aabb(&self) -> Option<AABB> {
+        match self {
+            Self::Leaf(None, _) => None,
+            Self::Leaf(Some(_), pos) => Some(AABB::new(
+                Vec3::new(pos.x0 as f32, pos.y0 as f32, pos.z0 as f32),
+                Vec3::new(pos.x1 as f32, pos.y1 as f32, pos.z1 as f32),
+            )),
+            Self::Inner(a, b, ..) => match (a.tight_aabb(), b.tight_aabb()) {
+                (Some(a), Some(b)) => Some(AABB::merge(&a, &b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
     fn count_leaves(&self) -> u32 {
         match self {
             Self::Leaf(..) => 1,
@@ -289,12 +497,42 @@ impl ChunkNode {
         }
     }
 
-    fn any_empty_in_region(&self, region: &ChunkNodePos) -> bool {
+    const fn count_solid(&self) -> u32 {
+        match self {
+            Self::Leaf(None, _) => 0,
+            Self::Leaf(Some(_), pos) => pos.volume() as u32,
+            Self::Inner(.., solid_count) => *solid_count,
+        }
+    }
+
+    fn any_empty_in_region(&self, region: ChunkNodePos) -> bool {
+        match self {
+            Self::Leaf(val, pos) => pos.intersects(region) && val.is_none(),
+            Self::Inner(a, b, _, pos, _) => {
+                pos.intersects(region)
+                    && (a.any_empty_in_region(region) || b.any_empty_in_region(region))
+            }
+        }
+    }
+
+    fn any_transparent_in_region(&self, region: ChunkNodePos) -> bool {
+        match self {
+            Self::Leaf(val, pos) => {
+                pos.intersects(region) && val.is_some_and(|block| block.is_transparent())
+            }
+            Self::Inner(a, b, _, pos, _) => {
+                pos.intersects(region)
+                    && (a.any_transparent_in_region(region) || b.any_transparent_in_region(region))
+            }
+        }
+    }
+
+    fn any_block_of_type(&self, region: ChunkNodePos, block: BlockType) -> bool {
         match self {
-            Self::Leaf(val, pos) => intersects(pos, region) && val.is_none(),
-            Self::Inner(a, b, _, pos) => {
-                intersects(pos, region) && a.any_empty_in_region(region)
-                    || b.any_empty_in_region(region)
+            Self::Leaf(val, pos) => pos.intersects(region) && *val == Some(block),
+            Self::Inner(a, b, _, pos, _) => {
+                pos.intersects(region)
+                    && (a.any_block_of_type(region, block) || b.any_block_of_type(region, block))
             }
         }
     }
@@ -310,7 +548,7 @@ impl ChunkNode {
     ) -> Option<BlockType> {
         match self {
             Self::Leaf(v, _size) => *v,
-            Self::Inner(a, b, dir, pos) => match dir {
+            Self::Inner(a, b, dir, pos, _) => match dir {
                 SplitDir::LeftRight => {
                     let midx = ox + pos.size_x() / 2;
                     if x < midx {
@@ -339,28 +577,19 @@ impl ChunkNode {
         }
     }
 
-    fn fill_blocks(&self, blocks: &mut Blocks) {
+    fn collect_leaves(&self, leaves: &mut Vec<(Option<BlockType>, ChunkNodePos)>) {
         match self {
-            Self::Leaf(block, pos) =>
-            {
-                #[expect(clippy::needless_range_loop)]
-                for x in pos.x0..pos.x1 {
-                    for y in pos.y0..pos.y1 {
-                        for z in pos.z0..pos.z1 {
-                            blocks[x][y][z] = *block;
-                        }
-                    }
-                }
-            }
-            Self::Inner(a, b, _, _) => {
-                a.fill_blocks(blocks);
-                b.fill_blocks(blocks);
+            &Self::Leaf(block, pos) => leaves.push((block, pos)),
+            Self::Inner(a, b, ..) => {
+                a.collect_leaves(leaves);
+                b.collect_leaves(leaves);
             }
         }
     }
 }
 
 // [start, end)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct ChunkNodePos {
     x0: usize,
     x1: usize,
@@ -400,6 +629,21 @@ impl ChunkNodePos {
     const fn size(&self) -> (usize, usize, usize) {
         (self.size_x(), self.size_y(), self.size_z())
     }
+
+    #[inline]
+    const fn volume(&self) -> usize {
+        self.size_x() * self.size_y() * self.size_z()
+    }
+
+    #[inline]
+    const fn intersects(&self, other: Self) -> bool {
+        self.x0 < other.x1
+            && self.x1 > other.x0
+            && self.y0 < other.y1
+            && self.y1 > other.y0
+            && self.z0 < other.z1
+            && self.z1 > other.z0
+    }
 }
 
 fn merge_if_same(
@@ -410,21 +654,24 @@ fn merge_if_same(
 ) -> ChunkNode {
     match (&*a, &*b) {
         (ChunkNode::Leaf(va, _), ChunkNode::Leaf(vb, _)) if va == vb => ChunkNode::Leaf(*va, pos),
-        _ => ChunkNode::Inner(a, b, dir, pos),
+        _ => {
+            let solid_count = a.count_solid() + b.count_solid();
+            ChunkNode::Inner(a, b, dir, pos, solid_count)
+        }
     }
 }
 
 #[expect(clippy::option_option)]
 fn uniform(
     blocks: &Blocks,
-    &ChunkNodePos {
+    ChunkNodePos {
         x0,
         x1,
         y0,
         y1,
         z0,
         z1,
-    }: &ChunkNodePos,
+    }: ChunkNodePos,
 ) -> Option<Option<BlockType>> {
     let first = blocks[x0][y0][z0];
     (x0..x1)
@@ -432,12 +679,19 @@ fn uniform(
         .then_some(first)
 }
 
-fn create_face_vertices(face: Face, block: BlockType, pos: &ChunkNodePos) -> [Vertex; 4] {
+fn create_face_vertices(
+    face: Face,
+    block: BlockType,
+    pos: ChunkNodePos,
+    ao: [f32; 4],
+) -> [Vertex; 4] {
     let size = pos.size();
     let (sx, sy, sz) = size;
 
     let face_uvs = face.uvs(size);
     let face_positions = face.positions();
+    // light sources ignore the shading and the occlusion of their faces
+    let emission = f32::from(block.light_emission()) / f32::from(MAX_LIGHT);
 
     std::array::from_fn(|i| Vertex {
         position: [
@@ -452,10 +706,108 @@ fn create_face_vertices(face: Face, block: BlockType, pos: &ChunkNodePos) -> [Ve
             Face::Bottom => block.atlas_offset_bottom(),
             Face::Left | Face::Right | Face::Front | Face::Back => block.atlas_offset_side(),
         },
+        light: face.light_level().max(emission),
+        ao: ao[i].max(emission),
     })
 }
 
-#[inline]
-const fn intersects(a: &ChunkNodePos, b: &ChunkNodePos) -> bool {
-    a.x0 < b.x1 && a.x1 > b.x0 && a.y0 < b.y1 && a.y1 > b.y0 && a.z0 < b.z1 && a.z1 > b.z0
+// (normal axis, first tangent axis, second tangent axis)
+const fn slice_axes(face: Face) -> (usize, usize, usize) {
+    let (u, v) = face.tangent_axes();
+    (face.axis(), u.axis(), v.axis())
+}
+
+const fn slice_cell(face: Face, slice: usize, u: usize, v: usize) -> [usize; 3] {
+    let (normal_axis, u_axis, v_axis) = slice_axes(face);
+    let mut cell = [0; 3];
+    cell[normal_axis] = slice;
+    cell[u_axis] = u;
+    cell[v_axis] = v;
+    cell
+}
+
+fn greedy_merge(
+    mask: &mut GreedyMask,
+    face: Face,
+    slice: usize,
+) -> Vec<(BlockType, ChunkNodePos, [f32; 4])> {
+    let (_, u_axis, v_axis) = slice_axes(face);
+    let (u_size, v_size) = (CHUNK_DIMS[u_axis], CHUNK_DIMS[v_axis]);
+    let mut quads = Vec::new();
+
+    for v in 0..v_size {
+        for u in 0..u_size {
+            // merged cells are cleared from the mask, so they are skipped here
+            let Some(key) = mask[v * u_size + u] else {
+                continue;
+            };
+
+            let mut width = 1;
+            while u + width < u_size && mask[v * u_size + u + width] == Some(key) {
+                width += 1;
+            }
+
+            let mut height = 1;
+            while v + height < v_size
+                && mask[(v + height) * u_size + u..(v + height) * u_size + u + width]
+                    .iter()
+                    .all(|&cell| cell == Some(key))
+            {
+                height += 1;
+            }
+
+            for row in v..v + height {
+                mask[row * u_size + u..row * u_size + u + width].fill(None);
+            }
+
+            let start = slice_cell(face, slice, u, v);
+            let end = slice_cell(face, slice + 1, u + width, v + height);
+            let pos = ChunkNodePos::new(start[0], end[0], start[1], end[1], start[2], end[2]);
+            let (block, ao) = key;
+            quads.push((block, pos, ao));
+        }
+    }
+
+    quads
+}
+
+// whether a face of `block` can be seen through `neighbor`
+fn shows_face(block: BlockType, neighbor: Option<BlockType>) -> bool {
+    neighbor.is_none_or(|neighbor| !block.is_transparent() && neighbor.is_transparent())
+}
+
+fn quad_indices(index_offset: u32, ao: [f32; 4]) -> [u32; 6] {
+    // flip the quad diagonal to avoid anisotropic AO interpolation
+    let [i0, i1, i2, i3] = if ao[0] + ao[2] >= ao[1] + ao[3] {
+        [0, 1, 2, 3]
+    } else {
+        [1, 2, 3, 0]
+    };
+    [i0, i1, i2, i2, i3, i0].map(|i| index_offset + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disjoint_solid_regions_have_no_empty_blocks() {
+        let stone = ChunkNodePos::new(0, CHUNK_WIDTH / 2, 0, CHUNK_WIDTH, 0, 10);
+        let dirt = ChunkNodePos::new(CHUNK_WIDTH / 2, CHUNK_WIDTH, 0, CHUNK_WIDTH, 0, 10);
+        let mut blocks = [[[None; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH];
+        for (region, block) in [(stone, BlockType::Stone), (dirt, BlockType::Dirt)] {
+            for plane in &mut blocks[region.x0..region.x1] {
+                for column in &mut plane[region.y0..region.y1] {
+                    column[region.z0..region.z1].fill(Some(block));
+                }
+            }
+        }
+        let chunk = Chunk::new(ChunkCoords::new(0, 0), blocks);
+
+        assert!(!chunk.root.any_empty_in_region(stone));
+        assert!(!chunk.root.any_empty_in_region(dirt));
+        // the air right above both regions
+        let above = ChunkNodePos::new(0, CHUNK_WIDTH, 0, CHUNK_WIDTH, 10, 11);
+        assert!(chunk.root.any_empty_in_region(above));
+    }
 }
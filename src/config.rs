@@ -0,0 +1,134 @@
+use {
+    crate::{noise::SimplexNoiseInfo, spline::SplinePoint},
+    serde::{Deserialize, Serialize},
+    std::{fs, io, path::Path},
+};
+
+// missing sections keep their default value
+// but a missing field inside a noise section falls back to SimplexNoiseInfo::default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    // affects hot vs cold biomes, unused with --voronoi-temp
+    pub temperature: SimplexNoiseInfo,
+    // affects dry vs wet biomes
+    pub humidity: SimplexNoiseInfo,
+    // determines land vs ocean
+    pub continentalness: SimplexNoiseInfo,
+    // affects terrain ruggedness
+    pub erosion: SimplexNoiseInfo,
+    // creates unusual terrain features
+    pub weirdness: SimplexNoiseInfo,
+    // carved wherever the 3d noise exceeds CAVE_THRESHOLD
+    pub cave: SimplexNoiseInfo,
+
+    // higher continentalness = higher terrain
+    pub continentalness_spline: Vec<SplinePoint>,
+    // higher erosion = lower, flatter terrain
+    pub erosion_spline: Vec<SplinePoint>,
+    pub peaks_valleys_spline: Vec<SplinePoint>,
+}
+
+impl WorldConfig {
+    // json if the extension says so, toml otherwise
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let config: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents).map_err(io::Error::other)?
+        } else {
+            toml::from_str(&contents).map_err(io::Error::other)?
+        };
+        config.validate().map_err(io::Error::other)?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        [
+            ("temperature", &self.temperature),
+            ("humidity", &self.humidity),
+            ("continentalness", &self.continentalness),
+            ("erosion", &self.erosion),
+            ("weirdness", &self.weirdness),
+            ("cave", &self.cave),
+        ]
+        .into_iter()
+        .try_for_each(|(name, info)| info.validate().map_err(|err| format!("{name}: {err}")))
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            temperature: SimplexNoiseInfo {
+                frequency: 0.000336,
+                octaves: 2,
+                ..Default::default()
+            },
+            humidity: SimplexNoiseInfo {
+                frequency: 0.000246,
+                octaves: 2,
+                persistence: 0.6,
+                ..Default::default()
+            },
+            continentalness: SimplexNoiseInfo {
+                frequency: 0.000974,
+                octaves: 6,
+                persistence: 0.8,
+                lacunarity: 1.2,
+                ..Default::default()
+            },
+            erosion: SimplexNoiseInfo {
+                frequency: 0.00998,
+                octaves: 6,
+                persistence: 0.42,
+                warp_strength: 40.0, // blurs the straight edges between biomes
+                ..Default::default()
+            },
+            weirdness: SimplexNoiseInfo {
+                frequency: 0.00196,
+                octaves: 6,
+                persistence: 0.66,
+                ..Default::default()
+            },
+            cave: SimplexNoiseInfo {
+                frequency: 0.02,
+                octaves: 3,
+                persistence: 0.5,
+                lacunarity: 2.0,
+                ..Default::default()
+            },
+
+            continentalness_spline: vec![
+                SplinePoint::new(-1.0, -40.0),
+                SplinePoint::new(-0.45, -20.0),
+                SplinePoint::new(-0.2, -2.0),
+                SplinePoint::new(-0.1, -1.0),
+                SplinePoint::new(0.15, 2.0),
+                SplinePoint::new(0.3, 8.0),
+                SplinePoint::new(0.5, 10.0),
+                SplinePoint::new(0.7, 18.0),
+                SplinePoint::new(0.8, 20.0),
+                SplinePoint::new(1.0, 30.0),
+            ],
+            erosion_spline: vec![
+                SplinePoint::new(-1.0, 1.0),
+                SplinePoint::new(-0.8, 0.9),
+                SplinePoint::new(-0.38, 0.8),
+                SplinePoint::new(-0.22, 0.6),
+                SplinePoint::new(0.05, 0.5),
+                SplinePoint::new(0.45, 0.4),
+                SplinePoint::new(0.9, 0.2),
+                SplinePoint::new(1.0, 0.1),
+            ],
+            peaks_valleys_spline: vec![
+                SplinePoint::new(-1.0, -30.0),
+                SplinePoint::new(-0.9, 0.0),
+                SplinePoint::new(-0.2, 2.0),
+                SplinePoint::new(0.2, 10.0),
+                SplinePoint::new(0.6, 30.0),
+                SplinePoint::new(0.9, 60.0),
+                SplinePoint::new(1.0, 60.0),
+            ],
+        }
+    }
+}
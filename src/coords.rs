@@ -1,13 +1,70 @@
 use {
     crate::chunk::{CHUNK_HEIGHT, CHUNK_WIDTH},
+    bincode::{Decode, Encode},
     glam::Vec3,
 };
 
-pub type WorldCoords = (i32, i32, i32);
-pub type ChunkCoords = (i32, i32);
-pub type BlockCoords = (usize, usize, usize);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct WorldCoords {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct ChunkCoords {
+    pub cx: i32,
+    pub cy: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct BlockCoords {
+    pub bx: usize,
+    pub by: usize,
+    pub bz: usize,
+}
+
+impl WorldCoords {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+}
 
-pub fn split_coords((x, y, z): WorldCoords) -> Option<(ChunkCoords, BlockCoords)> {
+impl ChunkCoords {
+    pub const fn new(cx: i32, cy: i32) -> Self {
+        Self { cx, cy }
+    }
+
+    pub const fn offset(self, dx: i32, dy: i32) -> Self {
+        Self::new(self.cx + dx, self.cy + dy)
+    }
+}
+
+impl BlockCoords {
+    pub const fn new(bx: usize, by: usize, bz: usize) -> Self {
+        Self { bx, by, bz }
+    }
+}
+
+impl From<(i32, i32, i32)> for WorldCoords {
+    fn from((x, y, z): (i32, i32, i32)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<(i32, i32)> for ChunkCoords {
+    fn from((cx, cy): (i32, i32)) -> Self {
+        Self::new(cx, cy)
+    }
+}
+
+impl From<(usize, usize, usize)> for BlockCoords {
+    fn from((bx, by, bz): (usize, usize, usize)) -> Self {
+        Self::new(bx, by, bz)
+    }
+}
+
+pub fn split_coords(WorldCoords { x, y, z }: WorldCoords) -> Option<(ChunkCoords, BlockCoords)> {
     let block_z = (z >= 0 && z < CHUNK_HEIGHT as i32).then_some(z as usize)?;
 
     let chunk_x = x.div_euclid(CHUNK_WIDTH as i32);
@@ -15,11 +72,34 @@ pub fn split_coords((x, y, z): WorldCoords) -> Option<(ChunkCoords, BlockCoords)
     let chunk_y = y.div_euclid(CHUNK_WIDTH as i32);
     let block_y = y.rem_euclid(CHUNK_WIDTH as i32) as usize;
 
-    Some(((chunk_x, chunk_y), (block_x, block_y, block_z)))
+    Some((
+        ChunkCoords::new(chunk_x, chunk_y),
+        BlockCoords::new(block_x, block_y, block_z),
+    ))
+}
+
+// the chunk of a block, plus its neighbors when the block lies on their shared edge
+pub fn affected_chunks(world_coords: WorldCoords) -> Vec<ChunkCoords> {
+    let Some((chunk_coords, BlockCoords { bx, by, .. })) = split_coords(world_coords) else {
+        return Vec::new();
+    };
+
+    let mut chunks = vec![chunk_coords];
+    if bx == 0 {
+        chunks.push(chunk_coords.offset(-1, 0));
+    } else if bx == CHUNK_WIDTH - 1 {
+        chunks.push(chunk_coords.offset(1, 0));
+    }
+    if by == 0 {
+        chunks.push(chunk_coords.offset(0, -1));
+    } else if by == CHUNK_WIDTH - 1 {
+        chunks.push(chunk_coords.offset(0, 1));
+    }
+    chunks
 }
 
 pub const fn camera_to_world_coords(camera_coords: Vec3) -> WorldCoords {
-    (
+    WorldCoords::new(
         camera_coords.x.floor() as i32,
         camera_coords.y.floor() as i32,
         camera_coords.z.floor() as i32,
@@ -29,13 +109,34 @@ pub const fn camera_to_world_coords(camera_coords: Vec3) -> WorldCoords {
 pub fn camera_to_chunk_coords(camera_coords: Vec3) -> ChunkCoords {
     let chunk_x = (camera_coords.x / CHUNK_WIDTH as f32).floor() as i32;
     let chunk_y = (camera_coords.y / CHUNK_WIDTH as f32).floor() as i32;
-    (chunk_x, chunk_y)
+    ChunkCoords::new(chunk_x, chunk_y)
 }
 
-pub const fn chunk_distance_squared((cx1, cy1): ChunkCoords, (cx2, cy2): ChunkCoords) -> i32 {
-    (cx1 - cx2).pow(2) + (cy1 - cy2).pow(2)
+pub const fn chunk_distance_squared(cc1: ChunkCoords, cc2: ChunkCoords) -> i32 {
+    (cc1.cx - cc2.cx).pow(2) + (cc1.cy - cc2.cy).pow(2)
 }
 
 pub fn chunk_distance(cc1: ChunkCoords, cc2: ChunkCoords) -> f32 {
     (chunk_distance_squared(cc1, cc2) as f32).sqrt()
 }
+
+// the chunks within `radius` of the center, row by row
+pub fn chunk_neighborhood(center: ChunkCoords, radius: f32) -> impl Iterator<Item = ChunkCoords> {
+    let rows = radius.floor() as i32;
+    (-rows..=rows).flat_map(move |dy| {
+        let max_dx = (radius * radius - (dy * dy) as f32).sqrt() as i32;
+        (-max_dx..=max_dx).map(move |dx| center.offset(dx, dy))
+    })
+}
+
+// the chunks at least `inner_radius` and at most `outer_radius` away from the center
+#[expect(dead_code)] // TODO: unload and lod zones
+pub fn chunk_ring(
+    center: ChunkCoords,
+    inner_radius: f32,
+    outer_radius: f32,
+) -> impl Iterator<Item = ChunkCoords> {
+    chunk_neighborhood(center, outer_radius).filter(move |&chunk_coords| {
+        chunk_distance_squared(center, chunk_coords) as f32 >= inner_radius * inner_radius
+    })
+}
@@ -29,6 +29,45 @@ impl Face {
         }
     }
 
+    // cheap static directional shading
+    pub const fn light_level(&self) -> f32 {
+        match self {
+            Self::Top => 1.0,
+            Self::Bottom => 0.4,
+            Self::Left | Self::Right => 0.7,
+            Self::Front | Self::Back => 0.6,
+        }
+    }
+
+    pub const fn opposite(&self) -> Self {
+        match self {
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Front => Self::Back,
+            Self::Back => Self::Front,
+        }
+    }
+
+    // index of the axis the normal lies on (x = 0, y = 1, z = 2)
+    pub const fn axis(&self) -> usize {
+        match self {
+            Self::Left | Self::Right => 0,
+            Self::Front | Self::Back => 1,
+            Self::Top | Self::Bottom => 2,
+        }
+    }
+
+    // positive faces of the two other axes, in cyclic order after this face's axis
+    pub const fn tangent_axes(&self) -> (Self, Self) {
+        match self {
+            Self::Left | Self::Right => (Self::Back, Self::Top),
+            Self::Front | Self::Back => (Self::Top, Self::Right),
+            Self::Top | Self::Bottom => (Self::Right, Self::Back),
+        }
+    }
+
     pub const fn positions(&self) -> [[f32; 3]; 4] {
         match self {
             Self::Right => [
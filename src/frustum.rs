@@ -22,6 +22,7 @@ impl Plane {
 #[derive(Debug, Clone)]
 pub struct Frustum {
     pub planes: [Plane; 6],
+    inverse_view_proj: Mat4,
 }
 
 impl Frustum {
@@ -76,14 +77,49 @@ impl Frustum {
 
         Self {
             planes: normalized_planes,
+            inverse_view_proj: view_proj.inverse(),
         }
     }
 
+    // near corners first, each as left-bottom, left-top, right-bottom, right-top
+    #[expect(dead_code)] // TODO: shadow cascades
+    pub fn corners(&self) -> [Vec3; 8] {
+        // wgpu clip space has a depth range of [0, 1]
+        [
+            (0.0, -1.0, -1.0),
+            (0.0, -1.0, 1.0),
+            (0.0, 1.0, -1.0),
+            (0.0, 1.0, 1.0),
+            (1.0, -1.0, -1.0),
+            (1.0, -1.0, 1.0),
+            (1.0, 1.0, -1.0),
+            (1.0, 1.0, 1.0),
+        ]
+        .map(|(z, x, y)| self.inverse_view_proj.project_point3(Vec3::new(x, y, z)))
+    }
+
+    #[expect(dead_code)]
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance_to_point(center) >= -radius)
+    }
+
     pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
         let center = aabb.center();
         let extents = aabb.extents();
+        let radius = extents.length();
 
         for plane in &self.planes {
+            // the bounding sphere settles most planes without looking at the box corners
+            let center_distance = plane.distance_to_point(center);
+            if center_distance < -radius {
+                return false;
+            }
+            if center_distance >= radius {
+                continue;
+            }
+
             let positive_vertex = center
                 + Vec3::new(
                     if plane.normal.x >= 0.0 {
@@ -0,0 +1,151 @@
+use std::sync::mpsc::{self, Receiver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuPass {
+    Skybox,
+    Voxels,
+    DepthOfField,
+    Overlay,
+}
+
+impl GpuPass {
+    pub const ALL: [Self; 4] = [
+        Self::Skybox,
+        Self::Voxels,
+        Self::DepthOfField,
+        Self::Overlay,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Skybox => "Skybox",
+            Self::Voxels => "Voxels",
+            Self::DepthOfField => "Depth of field",
+            Self::Overlay => "Overlay",
+        }
+    }
+
+    // a timestamp at the beginning and one at the end of each pass
+    const fn query_index(self) -> u32 {
+        2 * self as u32
+    }
+}
+
+const QUERY_COUNT: u32 = 2 * GpuPass::ALL.len() as u32;
+const BUFFER_SIZE: wgpu::BufferAddress = (QUERY_COUNT * wgpu::QUERY_SIZE) as wgpu::BufferAddress;
+
+// the timestamps are read back without stalling, so the durations lag a few frames behind
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    period: f32, // nanoseconds per tick
+    // the readback buffer can't be copied to while it is being mapped
+    is_copied: bool,
+    mapping: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    durations: [f32; GpuPass::ALL.len()], // milliseconds
+}
+
+impl GpuTimer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("gpu_timer_query_set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gpu_timer_resolve_buffer"),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gpu_timer_readback_buffer"),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            is_copied: false,
+            mapping: None,
+            durations: [0.0; GpuPass::ALL.len()],
+        }
+    }
+
+    pub const fn duration_ms(&self, pass: GpuPass) -> f32 {
+        self.durations[pass as usize]
+    }
+
+    pub const fn timestamp_writes(&self, pass: GpuPass) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(pass.query_index()),
+            end_of_pass_write_index: Some(pass.query_index() + 1),
+        }
+    }
+
+    // to be recorded after the passes, skipped while the previous timestamps are being read
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.mapping.is_some() {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            BUFFER_SIZE,
+        );
+        self.is_copied = true;
+    }
+
+    // to be called once the encoder holding `resolve` has been submitted
+    pub fn map(&mut self) {
+        if !self.is_copied {
+            return;
+        }
+        self.is_copied = false;
+        let (sender, receiver) = mpsc::channel();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if sender.send(result).is_err() {
+                    log::debug!("gpu timer dropped before its timestamps were read");
+                }
+            });
+        self.mapping = Some(receiver);
+    }
+
+    pub fn read_back(&mut self, device: &wgpu::Device) {
+        let Some(mapping) = &self.mapping else {
+            return;
+        };
+        if let Err(err) = device.poll(wgpu::PollType::Poll) {
+            log::warn!("failed to poll the device: {err}");
+        }
+        let Ok(result) = mapping.try_recv() else {
+            return;
+        };
+        self.mapping = None;
+        if let Err(err) = result {
+            log::warn!("failed to read the gpu timestamps: {err}");
+            return;
+        }
+
+        let timestamps: Vec<u64> =
+            bytemuck::pod_collect_to_vec(&self.readback_buffer.slice(..).get_mapped_range());
+        self.readback_buffer.unmap();
+        for pass in GpuPass::ALL {
+            let begin = timestamps[pass.query_index() as usize];
+            let end = timestamps[pass.query_index() as usize + 1];
+            // passes that didn't run this frame keep whatever was there
+            self.durations[pass as usize] = end.saturating_sub(begin) as f32 * self.period / 1e6;
+        }
+    }
+}
@@ -2,12 +2,18 @@ mod aabb;
 mod app;
 mod biome;
 mod block;
+mod buffer_pool;
 mod camera;
 mod chunk;
+mod config;
 mod coords;
 mod face;
 mod frustum;
+mod gpu_timer;
+mod modification_store;
 mod noise;
+mod raycast;
+mod shader;
 mod spline;
 mod state;
 mod texture;
@@ -16,8 +22,9 @@ mod vertex;
 mod world;
 
 use {
-    crate::app::Application,
+    crate::{app::Application, block::BlockType, world::WorldGeneratorKind},
     clap::Parser,
+    std::path::PathBuf,
     winit::event_loop::{ControlFlow, EventLoop},
 };
 
@@ -32,10 +39,61 @@ struct Args {
     normal_speed: f32,
     #[arg(long, default_value_t = 20.0)]
     boosted_speed: f32,
+    #[arg(long, default_value_t = 0.004)]
+    sensitivity: f32,
+    #[arg(long, default_value_t = 15.0)]
+    inertia: f32,
     #[arg(long = "no-vertical", action = clap::ArgAction::SetFalse)]
     vertical_enabled: bool,
     #[arg(long, default_value_t = 100)]
     slow_frame_warning_ms: u64,
+    #[arg(long)]
+    greedy_mesh: bool,
+    #[arg(long, default_value_t = 4)]
+    max_chunks_per_frame: usize,
+    #[arg(long)]
+    voronoi_temp: bool,
+    #[arg(long, default_value_t = 22.5)]
+    render_distance: f32,
+    #[arg(long, default_value_t = 80.0)]
+    fov: f32,
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    spawn_x: i32,
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    spawn_y: i32,
+    #[arg(long)]
+    save_path: Option<PathBuf>,
+    #[arg(long)]
+    config: Option<PathBuf>,
+    #[arg(long)]
+    flat: bool,
+    #[arg(long, default_value_t = 60)]
+    flat_height: usize,
+    #[arg(long, default_value = "stone", value_parser = parse_block_type)]
+    flat_block: BlockType,
+}
+
+fn parse_block_type(name: &str) -> Result<BlockType, String> {
+    BlockType::from_name(name).ok_or_else(|| format!("unknown block {name:?}"))
+}
+
+impl Args {
+    fn save_file(&self) -> PathBuf {
+        self.save_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("world_{}.sav", self.seed)))
+    }
+
+    const fn generator_kind(&self) -> WorldGeneratorKind {
+        if self.flat {
+            WorldGeneratorKind::Flat {
+                height: self.flat_height,
+                block: self.flat_block,
+            }
+        } else {
+            WorldGeneratorKind::Default
+        }
+    }
 }
 
 fn main() {
@@ -0,0 +1,111 @@
+use {
+    crate::{
+        block::BlockType,
+        coords::{BlockCoords, ChunkCoords},
+        state::cache_dir,
+    },
+    bincode::config,
+    std::{
+        collections::{HashMap, HashSet},
+        fs::{self, File},
+        io::{self, BufReader, BufWriter},
+        path::{Path, PathBuf},
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+// the deleted and the placed blocks of a single chunk
+pub type ChunkModifications = (HashSet<BlockCoords>, HashMap<BlockCoords, BlockType>);
+
+static NEXT_STORE_ID: AtomicUsize = AtomicUsize::new(0);
+
+// modifications of the chunks far from the player, one file per chunk until they come back
+// into range, the directory only lives as long as the store
+pub struct ModificationStore {
+    dir: Option<PathBuf>,
+    id: usize,
+    chunks: HashSet<ChunkCoords>,
+}
+
+impl ModificationStore {
+    pub fn new() -> Self {
+        Self {
+            dir: None,
+            id: NEXT_STORE_ID.fetch_add(1, Ordering::Relaxed),
+            chunks: HashSet::new(),
+        }
+    }
+
+    // created on the first eviction, a leftover of a crashed process with the same pid is cleared
+    fn dir(&mut self) -> io::Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+        let dir = cache_dir()
+            .ok_or_else(|| io::Error::other("no cache directory"))?
+            .join(format!("modifications_{}_{}", process::id(), self.id));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        self.dir = Some(dir.clone());
+        Ok(dir)
+    }
+
+    fn chunk_path(dir: &Path, ChunkCoords { cx, cy }: ChunkCoords) -> PathBuf {
+        dir.join(format!("{cx}_{cy}.bin"))
+    }
+
+    pub fn store(
+        &mut self,
+        chunk_coords: ChunkCoords,
+        modifications: &ChunkModifications,
+    ) -> io::Result<()> {
+        let path = Self::chunk_path(&self.dir()?, chunk_coords);
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::encode_into_std_write(modifications, &mut writer, config::standard())
+            .map_err(io::Error::other)?;
+        self.chunks.insert(chunk_coords);
+        Ok(())
+    }
+
+    fn read(&self, chunk_coords: ChunkCoords) -> io::Result<ChunkModifications> {
+        let dir = self
+            .dir
+            .as_ref()
+            .ok_or_else(|| io::Error::other("empty store"))?;
+        let mut reader = BufReader::new(File::open(Self::chunk_path(dir, chunk_coords))?);
+        bincode::decode_from_std_read(&mut reader, config::standard()).map_err(io::Error::other)
+    }
+
+    // the file is removed, the caller keeps the modifications in memory again
+    pub fn take(&mut self, chunk_coords: ChunkCoords) -> io::Result<Option<ChunkModifications>> {
+        if !self.chunks.contains(&chunk_coords) {
+            return Ok(None);
+        }
+        let modifications = self.read(chunk_coords)?;
+        self.chunks.remove(&chunk_coords);
+        if let Some(dir) = &self.dir {
+            fs::remove_file(Self::chunk_path(dir, chunk_coords))?;
+        }
+        Ok(Some(modifications))
+    }
+
+    pub fn read_all(&self) -> io::Result<Vec<(ChunkCoords, ChunkModifications)>> {
+        self.chunks
+            .iter()
+            .map(|&chunk_coords| Ok((chunk_coords, self.read(chunk_coords)?)))
+            .collect()
+    }
+}
+
+impl Drop for ModificationStore {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir
+            && let Err(err) = fs::remove_dir_all(dir)
+        {
+            log::warn!("failed to remove {}: {err}", dir.display());
+        }
+    }
+}
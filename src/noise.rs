@@ -1,8 +1,16 @@
+use {
+    crate::utils::prf_i32x3_mod,
+    serde::{Deserialize, Serialize},
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SimplexNoiseInfo {
     pub frequency: f32,
     pub octaves: usize,
     pub persistence: f32,
     pub lacunarity: f32,
+    pub warp_strength: f32,
 }
 
 impl Default for SimplexNoiseInfo {
@@ -12,16 +20,46 @@ impl Default for SimplexNoiseInfo {
             octaves: 4,
             persistence: 0.4,
             lacunarity: 1.8,
+            warp_strength: 0.0,
         }
     }
 }
 
+impl SimplexNoiseInfo {
+    // bad parameters don't crash anything, they just produce flat or noisy garbage
+    pub fn validate(&self) -> Result<(), String> {
+        if self.frequency <= 0.0 {
+            return Err(format!(
+                "frequency must be positive, got {}",
+                self.frequency
+            ));
+        }
+        if self.octaves == 0 {
+            return Err("octaves must be at least 1, got 0".to_owned());
+        }
+        if self.persistence <= 0.0 || self.persistence > 1.0 {
+            return Err(format!(
+                "persistence must be in (0, 1], got {}",
+                self.persistence
+            ));
+        }
+        if self.lacunarity < 1.0 {
+            return Err(format!(
+                "lacunarity must be at least 1, got {}",
+                self.lacunarity
+            ));
+        }
+        Ok(())
+    }
+}
+
 pub struct SimplexNoise {
     permutations: [u8; 512],
     frequency: f32,
     octaves: usize,
     persistence: f32,
     lacunarity: f32,
+    warp_strength: f32,
 }
 
 impl SimplexNoise {
@@ -29,8 +67,15 @@ impl SimplexNoise {
     const F2: f32 = 0.36602542; // (sqrt(3) - 1) / 2
     const G2: f32 = 0.21132487; // (3 - sqrt(3)) / 6
 
-    #[expect(clippy::needless_pass_by_value)]
+    // Constants for 3D simplex noise
+    const F3: f32 = 1.0 / 3.0;
+    const G3: f32 = 1.0 / 6.0;
+
     pub fn new(seed: u64, info: SimplexNoiseInfo) -> Self {
+        if cfg!(debug_assertions) {
+            info.validate().expect("invalid noise parameters");
+        }
+
         let mut permutations = [0u8; 512];
         let mut temp = (0i32..256).map(|x| x as u8).collect::<Vec<u8>>();
 
@@ -55,10 +100,27 @@ impl SimplexNoise {
             octaves: info.octaves,
             persistence: info.persistence,
             lacunarity: info.lacunarity,
+            warp_strength: info.warp_strength,
         }
     }
 
     pub fn noise2d(&self, x: f32, y: f32) -> f32 {
+        if self.warp_strength == 0.0 {
+            self.fbm2d(x, y)
+        } else {
+            self.warped_noise2d(x, y, self.warp_strength)
+        }
+    }
+
+    // domain warping: offsets the input by two other samples of the same noise
+    pub fn warped_noise2d(&self, x: f32, y: f32, warp_strength: f32) -> f32 {
+        // the offsets are in noise space so that wx and wy are uncorrelated
+        let wx = self.fbm2d(x + 1.3 / self.frequency, y + 7.7 / self.frequency);
+        let wy = self.fbm2d(x + 8.9 / self.frequency, y + 2.6 / self.frequency);
+        self.fbm2d(x + warp_strength * wx, y + warp_strength * wy)
+    }
+
+    fn fbm2d(&self, x: f32, y: f32) -> f32 {
         let mut value = 0.0;
         let mut amplitude = 1.0;
         let mut frequency = self.frequency;
@@ -83,7 +145,67 @@ impl SimplexNoise {
         value.clamp(-1.0, 1.0)
     }
 
+    // (value, d/dx, d/dy) of the unwarped fbm, the derivatives are per world unit
+    #[expect(dead_code)] // TODO: normals or domain warping from the gradient
+    pub fn noise2d_deriv(&self, x: f32, y: f32) -> (f32, f32, f32) {
+        let (mut value, mut dx, mut dy) = (0.0, 0.0, 0.0);
+        let mut amplitude = 1.0;
+        let mut frequency = self.frequency;
+        let mut max_value = 0.0;
+
+        for _ in 0..self.octaves {
+            let (noise_value, gradient_x, gradient_y) =
+                self.simplex2d_deriv(x * frequency, y * frequency);
+            value += noise_value * amplitude;
+            // chain rule on the sample position
+            dx += gradient_x * amplitude * frequency;
+            dy += gradient_y * amplitude * frequency;
+            max_value += amplitude;
+
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        if max_value > 0.0 {
+            value /= max_value;
+            dx /= max_value;
+            dy /= max_value;
+        }
+
+        (value.clamp(-1.0, 1.0), dx, dy)
+    }
+
+    pub fn noise3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let mut value = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = self.frequency;
+        let mut max_value = 0.0;
+
+        for _ in 0..self.octaves {
+            let sample_x = x * frequency;
+            let sample_y = y * frequency;
+            let sample_z = z * frequency;
+
+            let noise_value = self.simplex3d(sample_x, sample_y, sample_z);
+            value += noise_value * amplitude;
+            max_value += amplitude;
+
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        if max_value > 0.0 {
+            value /= max_value;
+        }
+
+        value.clamp(-1.0, 1.0)
+    }
+
     fn simplex2d(&self, x: f32, y: f32) -> f32 {
+        self.simplex2d_deriv(x, y).0
+    }
+
+    fn simplex2d_deriv(&self, x: f32, y: f32) -> (f32, f32, f32) {
         let s = (x + y) * Self::F2;
         let i = (x + s).floor();
         let j = (y + s).floor();
@@ -114,28 +236,107 @@ impl SimplexNoise {
             as usize
             % 12;
 
-        let mut n0 = 0.0;
-        let t0 = 0.5 - x0 * x0 - y0 * y0;
-        if t0 >= 0.0 {
-            let t0_sq = t0 * t0;
-            n0 = t0_sq * t0_sq * Self::dot2d(gi0, x0, y0);
-        }
+        // n = t^4 (g.r) with t = 0.5 - r.r, so dn/dr = t^4 g - 8 t^3 (g.r) r
+        let corner = |gi: usize, x: f32, y: f32| -> (f32, f32, f32) {
+            let t = 0.5 - x * x - y * y;
+            if t < 0.0 {
+                (0.0, 0.0, 0.0)
+            } else {
+                let grad = Self::GRADIENT_2D[gi];
+                let dot = Self::dot2d(gi, x, y);
+                let t_sq = t * t;
+                let t_4 = t_sq * t_sq;
+                let slope = 8.0 * t_sq * t * dot;
+                (
+                    t_4 * dot,
+                    t_4 * grad.0 - slope * x,
+                    t_4 * grad.1 - slope * y,
+                )
+            }
+        };
 
-        let mut n1 = 0.0;
-        let t1 = 0.5 - x1 * x1 - y1 * y1;
-        if t1 >= 0.0 {
-            let t1_sq = t1 * t1;
-            n1 = t1_sq * t1_sq * Self::dot2d(gi1, x1, y1);
-        }
+        let n0 = corner(gi0, x0, y0);
+        let n1 = corner(gi1, x1, y1);
+        let n2 = corner(gi2, x2, y2);
 
-        let mut n2 = 0.0;
-        let t2 = 0.5 - x2 * x2 - y2 * y2;
-        if t2 >= 0.0 {
-            let t2_sq = t2 * t2;
-            n2 = t2_sq * t2_sq * Self::dot2d(gi2, x2, y2);
-        }
+        (
+            70.0 * (n0.0 + n1.0 + n2.0),
+            70.0 * (n0.1 + n1.1 + n2.1),
+            70.0 * (n0.2 + n1.2 + n2.2),
+        )
+    }
+
+    fn simplex3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let s = (x + y + z) * Self::F3;
+        let i = (x + s).floor();
+        let j = (y + s).floor();
+        let k = (z + s).floor();
+
+        let t = (i + j + k) * Self::G3;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+        let z0 = z - (k - t);
+
+        // find which of the six tetrahedra of the skewed cube we are in
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let x1 = x0 - i1 as f32 + Self::G3;
+        let y1 = y0 - j1 as f32 + Self::G3;
+        let z1 = z0 - k1 as f32 + Self::G3;
+
+        let x2 = x0 - i2 as f32 + 2.0 * Self::G3;
+        let y2 = y0 - j2 as f32 + 2.0 * Self::G3;
+        let z2 = z0 - k2 as f32 + 2.0 * Self::G3;
+
+        let x3 = x0 - 1.0 + 3.0 * Self::G3;
+        let y3 = y0 - 1.0 + 3.0 * Self::G3;
+        let z3 = z0 - 1.0 + 3.0 * Self::G3;
+
+        let ii = (i as i32 & 255) as usize;
+        let jj = (j as i32 & 255) as usize;
+        let kk = (k as i32 & 255) as usize;
+
+        let hash = |di: usize, dj: usize, dk: usize| -> usize {
+            let pk = self.permutations[kk + dk] as usize;
+            let pj = self.permutations[jj + dj + pk] as usize;
+            self.permutations[ii + di + pj] as usize % 12
+        };
+
+        let gi0 = hash(0, 0, 0);
+        let gi1 = hash(i1, j1, k1);
+        let gi2 = hash(i2, j2, k2);
+        let gi3 = hash(1, 1, 1);
+
+        let corner = |gi: usize, x: f32, y: f32, z: f32| -> f32 {
+            let t = 0.6 - x * x - y * y - z * z;
+            if t < 0.0 {
+                0.0
+            } else {
+                let t_sq = t * t;
+                t_sq * t_sq * Self::dot3d(gi, x, y, z)
+            }
+        };
+
+        let n0 = corner(gi0, x0, y0, z0);
+        let n1 = corner(gi1, x1, y1, z1);
+        let n2 = corner(gi2, x2, y2, z2);
+        let n3 = corner(gi3, x3, y3, z3);
 
-        70.0 * (n0 + n1 + n2)
+        32.0 * (n0 + n1 + n2 + n3)
     }
 
     const GRADIENT_2D: [(f32, f32); 12] = [
@@ -157,4 +358,78 @@ impl SimplexNoise {
         let grad = Self::GRADIENT_2D[gi];
         grad.0 * x + grad.1 * y
     }
+
+    const GRADIENT_3D: [(f32, f32, f32); 12] = [
+        (1.0, 1.0, 0.0),
+        (-1.0, 1.0, 0.0),
+        (1.0, -1.0, 0.0),
+        (-1.0, -1.0, 0.0),
+        (1.0, 0.0, 1.0),
+        (-1.0, 0.0, 1.0),
+        (1.0, 0.0, -1.0),
+        (-1.0, 0.0, -1.0),
+        (0.0, 1.0, 1.0),
+        (0.0, -1.0, 1.0),
+        (0.0, 1.0, -1.0),
+        (0.0, -1.0, -1.0),
+    ];
+
+    fn dot3d(gi: usize, x: f32, y: f32, z: f32) -> f32 {
+        let grad = Self::GRADIENT_3D[gi];
+        grad.0 * x + grad.1 * y + grad.2 * z
+    }
+}
+
+// worley noise on a jittered grid: one feature point per cell
+pub struct VoronoiNoise {
+    seed: u64,
+    frequency: f32,
+}
+
+impl VoronoiNoise {
+    const JITTER_RESOLUTION: u64 = 1 << 16;
+
+    pub const fn new(seed: u64, frequency: f32) -> Self {
+        Self { seed, frequency }
+    }
+
+    // distance to the nearest feature point in cells, rarely above 1, mapped to [-1, 1]
+    pub fn noise2d(&self, x: f32, y: f32) -> f32 {
+        let (x, y) = (x * self.frequency, y * self.frequency);
+        let (cell_x, cell_y) = (x.floor() as i32, y.floor() as i32);
+
+        let mut f1 = f32::INFINITY;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let (point_x, point_y) = self.feature_point(cell_x + dx, cell_y + dy);
+                f1 = f1.min(f32::hypot(point_x - x, point_y - y));
+            }
+        }
+
+        f1.mul_add(2.0, -1.0).clamp(-1.0, 1.0)
+    }
+
+    fn feature_point(&self, cell_x: i32, cell_y: i32) -> (f32, f32) {
+        let salt = self.seed as i32;
+        let jitter = |axis: i32| {
+            prf_i32x3_mod((cell_x, cell_y, salt ^ axis), Self::JITTER_RESOLUTION) as f32
+                / Self::JITTER_RESOLUTION as f32
+        };
+        (cell_x as f32 + jitter(0), cell_y as f32 + jitter(1))
+    }
+}
+
+// lets a generator swap the kind of noise behind one of its parameters
+pub enum Noise2d {
+    Simplex(Box<SimplexNoise>),
+    Voronoi(VoronoiNoise),
+}
+
+impl Noise2d {
+    pub fn noise2d(&self, x: f32, y: f32) -> f32 {
+        match self {
+            Self::Simplex(noise) => noise.noise2d(x, y),
+            Self::Voronoi(noise) => noise.noise2d(x, y),
+        }
+    }
 }
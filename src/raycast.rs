@@ -0,0 +1,131 @@
+use {
+    crate::{
+        block::BlockType,
+        chunk::Chunk,
+        coords::{BlockCoords, WorldCoords, camera_to_world_coords, split_coords},
+        face::Face,
+        utils::sign,
+        world::World,
+    },
+    glam::Vec3,
+};
+
+// walks the blocks crossed by a ray (Amanatides & Woo), yielding the solid ones in order
+// TODO: update DDA to use the tree structure of Chunk
+pub struct RaycastIter<'a> {
+    world: &'a World,
+    coords: [i32; 3],
+    step: [i32; 3],
+    t_max: Vec3,
+    t_delta: Vec3,
+    max_distance: f32,
+    started: bool,
+    finished: bool,
+}
+
+impl<'a> RaycastIter<'a> {
+    pub fn new(world: &'a World, pos: Vec3, dir: Vec3, max_distance: f32) -> Self {
+        let WorldCoords { x, y, z } = camera_to_world_coords(pos);
+        let coords = [x, y, z];
+        let step = [sign(dir.x), sign(dir.y), sign(dir.z)];
+
+        let mut t_max = Vec3::INFINITY;
+        let mut t_delta = Vec3::INFINITY;
+        for axis in 0..3 {
+            if step[axis] != 0 {
+                let next_boundary = (coords[axis] + (step[axis] > 0) as i32) as f32;
+                t_max[axis] = (next_boundary - pos[axis]) / dir[axis];
+                t_delta[axis] = (1.0 / dir[axis]).abs();
+            }
+        }
+
+        Self {
+            world,
+            coords,
+            step,
+            t_max,
+            t_delta,
+            max_distance,
+            started: false,
+            finished: false,
+        }
+    }
+
+    const fn world_coords(&self) -> WorldCoords {
+        WorldCoords::new(self.coords[0], self.coords[1], self.coords[2])
+    }
+
+    // None once the ray leaves its range, the world or the loaded chunks
+    fn loaded_chunk(&self, t: f32, world_coords: WorldCoords) -> Option<(&'a Chunk, BlockCoords)> {
+        if t > self.max_distance {
+            return None;
+        }
+        let (chunk_coords, block_coords) = split_coords(world_coords)?;
+        Some((self.world.get_chunk_if_loaded(chunk_coords)?, block_coords))
+    }
+
+    // a ray starting inside a block hits it right away
+    fn starting_block(&self) -> Option<(f32, WorldCoords, BlockType)> {
+        let world_coords = self.world_coords();
+        let block = self.world.get_block(world_coords)?;
+        Some((0.0, world_coords, block))
+    }
+}
+
+impl Iterator for RaycastIter<'_> {
+    type Item = (f32, WorldCoords, BlockType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if let Some(hit) = self.starting_block() {
+                return Some(hit);
+            }
+        }
+
+        while !self.finished {
+            let axis = if self.t_max.x < self.t_max.y {
+                if self.t_max.x < self.t_max.z { 0 } else { 2 }
+            } else if self.t_max.y < self.t_max.z {
+                1
+            } else {
+                2
+            };
+            let t = self.t_max[axis];
+            self.coords[axis] += self.step[axis];
+            self.t_max[axis] += self.t_delta[axis];
+
+            let world_coords = self.world_coords();
+            let Some((chunk, block_coords)) = self.loaded_chunk(t, world_coords) else {
+                self.finished = true;
+                break;
+            };
+            if let Some(block) = chunk.get_block(block_coords) {
+                return Some((t, world_coords, block));
+            }
+        }
+
+        None
+    }
+}
+
+// the face the ray enters the block through, the last of its boundaries to be crossed (slab method)
+// a ray starting inside the block gets the face it would have come through
+pub fn entry_face(pos: Vec3, dir: Vec3, WorldCoords { x, y, z }: WorldCoords) -> Face {
+    let min = Vec3::new(x as f32, y as f32, z as f32);
+    let entry = Vec3::select(dir.cmpgt(Vec3::ZERO), min, min + Vec3::ONE);
+    let t_enter = Vec3::select(
+        dir.cmpeq(Vec3::ZERO),
+        Vec3::NEG_INFINITY,
+        (entry - pos) / dir,
+    );
+    let faces = [
+        if dir.x > 0.0 { Face::Right } else { Face::Left },
+        if dir.y > 0.0 { Face::Back } else { Face::Front },
+        if dir.z > 0.0 { Face::Top } else { Face::Bottom },
+    ];
+    let axis = (0..3)
+        .max_by(|&a, &b| t_enter[a].total_cmp(&t_enter[b]))
+        .unwrap_or(0);
+    faces[axis].opposite()
+}
@@ -0,0 +1,107 @@
+use {
+    notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _},
+    std::{
+        collections::HashSet,
+        fs, io,
+        path::Path,
+        sync::mpsc::{self, Receiver},
+    },
+};
+
+// only meaningful when running from the repository
+const SHADERS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    Voxels,
+    Skybox,
+    Crosshair,
+    DepthOfField,
+    FrameGraph,
+    Minimap,
+}
+
+impl ShaderKind {
+    pub const ALL: [Self; 6] = [
+        Self::Voxels,
+        Self::Skybox,
+        Self::Crosshair,
+        Self::DepthOfField,
+        Self::FrameGraph,
+        Self::Minimap,
+    ];
+
+    pub const fn file_name(&self) -> &'static str {
+        match self {
+            Self::Voxels => "voxels.wgsl",
+            Self::Skybox => "skybox.wgsl",
+            Self::Crosshair => "crosshair.wgsl",
+            Self::DepthOfField => "dof.wgsl",
+            Self::FrameGraph => "frame_graph.wgsl",
+            Self::Minimap => "minimap.wgsl",
+        }
+    }
+
+    // the version compiled into the binary
+    pub const fn embedded_source(&self) -> &'static str {
+        match self {
+            Self::Voxels => include_str!("../shaders/voxels.wgsl"),
+            Self::Skybox => include_str!("../shaders/skybox.wgsl"),
+            Self::Crosshair => include_str!("../shaders/crosshair.wgsl"),
+            Self::DepthOfField => include_str!("../shaders/dof.wgsl"),
+            Self::FrameGraph => include_str!("../shaders/frame_graph.wgsl"),
+            Self::Minimap => include_str!("../shaders/minimap.wgsl"),
+        }
+    }
+
+    pub fn read_source(&self) -> io::Result<String> {
+        fs::read_to_string(Path::new(SHADERS_DIR).join(self.file_name()))
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?;
+        Self::ALL
+            .into_iter()
+            .find(|kind| file_name == kind.file_name())
+    }
+}
+
+pub struct ShaderWatcher {
+    _watcher: RecommendedWatcher, // stops watching when dropped
+    receiver: Receiver<ShaderKind>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            if !event.kind.is_modify() && !event.kind.is_create() {
+                return;
+            }
+            for kind in event
+                .paths
+                .iter()
+                .filter_map(|path| ShaderKind::from_path(path))
+            {
+                // the receiver is only gone when the application is shutting down
+                if sender.send(kind).is_err() {
+                    return;
+                }
+            }
+        })?;
+        watcher.watch(Path::new(SHADERS_DIR), RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    // editors usually write a file several times per save
+    pub fn changed_shaders(&self) -> HashSet<ShaderKind> {
+        self.receiver.try_iter().collect()
+    }
+}
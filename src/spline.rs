@@ -1,6 +1,9 @@
-use crate::utils::lerp;
+use {
+    crate::utils::{inverse_lerp, lerp},
+    serde::{Deserialize, Serialize},
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SplinePoint {
     pub x: f32, // Input
     pub y: f32, // Output
@@ -12,14 +15,30 @@ impl SplinePoint {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SplineKind {
+    #[default]
+    Linear,
+    CatmullRom,
+}
+
 #[derive(Debug, Clone)]
 pub struct Spline {
     points: Vec<SplinePoint>,
+    kind: SplineKind,
 }
 
 impl Spline {
     pub fn new(points: Vec<SplinePoint>) -> Self {
-        let mut spline = Self { points };
+        Self::with_kind(points, SplineKind::default())
+    }
+
+    pub fn catmull_rom(points: Vec<SplinePoint>) -> Self {
+        Self::with_kind(points, SplineKind::CatmullRom)
+    }
+
+    fn with_kind(points: Vec<SplinePoint>, kind: SplineKind) -> Self {
+        let mut spline = Self { points, kind };
         spline.sort_points();
         spline
     }
@@ -28,6 +47,13 @@ impl Spline {
         self.points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
     }
 
+    // slope at knot i, one-sided at the endpoints
+    fn tangent(&self, i: usize) -> f32 {
+        let prev = &self.points[i.saturating_sub(1)];
+        let next = &self.points[(i + 1).min(self.points.len() - 1)];
+        (next.y - prev.y) / (next.x - prev.x)
+    }
+
     pub fn sample(&self, x: f32) -> f32 {
         if self.points.is_empty() {
             return 0.0;
@@ -50,11 +76,62 @@ impl Spline {
             let p2 = &self.points[i + 1];
 
             if x >= p1.x && x <= p2.x {
-                let t = (x - p1.x) / (p2.x - p1.x);
-                return lerp(p1.y, p2.y, t);
+                let t = inverse_lerp(p1.x, p2.x, x);
+                return match self.kind {
+                    SplineKind::Linear => lerp(p1.y, p2.y, t),
+                    SplineKind::CatmullRom => {
+                        // cubic hermite basis
+                        let h = p2.x - p1.x;
+                        let t2 = t * t;
+                        let t3 = t2 * t;
+                        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+                        let h10 = t3 - 2.0 * t2 + t;
+                        let h01 = -2.0 * t3 + 3.0 * t2;
+                        let h11 = t3 - t2;
+                        h00 * p1.y
+                            + h10 * h * self.tangent(i)
+                            + h01 * p2.y
+                            + h11 * h * self.tangent(i + 1)
+                    }
+                };
             }
         }
 
         self.points.last().unwrap().y
     }
+
+    // slope of the curve at x, zero outside the knots where sample is clamped
+    pub fn derivative(&self, x: f32) -> f32 {
+        if self.points.len() < 2 || x <= self.points[0].x || x >= self.points.last().unwrap().x {
+            return 0.0;
+        }
+
+        for i in 0..self.points.len() - 1 {
+            let p1 = &self.points[i];
+            let p2 = &self.points[i + 1];
+
+            if x >= p1.x && x <= p2.x {
+                let h = p2.x - p1.x;
+                return match self.kind {
+                    SplineKind::Linear => (p2.y - p1.y) / h,
+                    SplineKind::CatmullRom => {
+                        // derivative of the hermite basis, rescaled from t to x
+                        let t = inverse_lerp(p1.x, p2.x, x);
+                        let t2 = t * t;
+                        let d00 = 6.0 * t2 - 6.0 * t;
+                        let d10 = 3.0 * t2 - 4.0 * t + 1.0;
+                        let d01 = -6.0 * t2 + 6.0 * t;
+                        let d11 = 3.0 * t2 - 2.0 * t;
+                        (d00 * p1.y
+                            + d10 * h * self.tangent(i)
+                            + d01 * p2.y
+                            + d11 * h * self.tangent(i + 1))
+                            / h
+                    }
+                };
+            }
+        }
+
+        0.0
+    }
 }
@@ -2,17 +2,32 @@ use {
     crate::{
         Args,
         aabb::AABB,
+        biome::BiomeType,
+        block::BlockType,
+        buffer_pool::BufferPool,
         camera::{CAMERA_NEAR, Camera, CameraController, CameraUniform, camera_far},
-        chunk::CHUNK_WIDTH,
-        coords::{ChunkCoords, camera_to_chunk_coords, chunk_distance},
+        chunk::{CHUNK_WIDTH, ChunkMesh, Mesh},
+        coords::{
+            ChunkCoords, WorldCoords, affected_chunks, camera_to_chunk_coords,
+            camera_to_world_coords, chunk_distance, chunk_distance_squared, chunk_neighborhood,
+        },
+        gpu_timer::{GpuPass, GpuTimer},
+        shader::ShaderKind,
         texture::Texture,
+        utils::{inverse_lerp, lerp, smoothstep},
         vertex::Vertex,
-        world::{MAX_DELETE_DISTANCE, World},
+        world::{DAY_LENGTH, MAX_DELETE_DISTANCE, NoiseValues, World},
     },
-    glam::Vec3,
+    glam::{Vec3, Vec4},
+    image::{ImageFormat, RgbaImage},
     std::{
+        cmp::Reverse,
         collections::{HashMap, HashSet},
-        sync::Arc,
+        env, fs,
+        hash::{DefaultHasher, Hash as _, Hasher as _},
+        io::Cursor,
+        path::PathBuf,
+        sync::{Arc, mpsc},
         time::Duration,
     },
     wgpu::{ExperimentalFeatures, util::DeviceExt as _},
@@ -23,16 +38,96 @@ use {
     winit::{dpi::PhysicalSize, window::Window},
 };
 
-pub const RENDER_DISTANCE: f32 = 22.5;
-pub const MEMORY_DISTANCE: i32 = 50;
-
-pub struct ChunkRenderData {
+const MIN_RENDER_DISTANCE: f32 = 4.0;
+const MAX_RENDER_DISTANCE: f32 = 64.0;
+const RENDER_DISTANCE_STEP: f32 = 2.0;
+
+// chunks beyond the memory distance are discarded every frame, so it has to cover
+// the max render distance plus the neighbors needed to mesh the farthest chunks
+pub const MEMORY_DISTANCE: i32 = MAX_RENDER_DISTANCE as i32 + 2;
+
+const HOTBAR: [BlockType; 9] = [
+    BlockType::Stone,
+    BlockType::Dirt,
+    BlockType::Grass,
+    BlockType::Sand,
+    BlockType::Wood,
+    BlockType::Leaves,
+    BlockType::Snow,
+    BlockType::Ice,
+    BlockType::Basalt,
+];
+
+const MINIMAP_SIZE: u32 = 128;
+const MINIMAP_MARGIN: u32 = 12;
+const MINIMAP_BLOCKS_PER_PIXEL: i32 = 4; // has to divide CHUNK_WIDTH
+
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+pub const FRAME_GRAPH_LEN: usize = 60;
+// must match the shader
+const FRAME_GRAPH_HEIGHT: f32 = 40.0;
+const FRAME_GRAPH_MARGIN: f32 = 12.0;
+
+const DAY_HORIZON_COLOR: Vec3 = Vec3::new(0.70, 0.82, 0.95);
+const DAY_ZENITH_COLOR: Vec3 = Vec3::new(0.24, 0.48, 0.88);
+const SUNSET_HORIZON_COLOR: Vec3 = Vec3::new(0.95, 0.50, 0.25);
+const NIGHT_HORIZON_COLOR: Vec3 = Vec3::new(0.02, 0.02, 0.05);
+const NIGHT_ZENITH_COLOR: Vec3 = Vec3::new(0.0, 0.0, 0.02);
+// sun heights (z of its direction) between which the sky goes from night to day, as in skybox.wgsl
+const TWILIGHT: (f32, f32) = (-0.15, 0.3);
+// the horizon turns orange while the sun is lower than this
+const SUNSET_HEIGHT: f32 = 0.25;
+
+// close to the average color of the skybox, the atmosphere fades from there to the biome colors
+const INITIAL_ATMOSPHERE_COLOR: Vec3 = Vec3::new(0.01, 0.01, 0.03);
+const ATMOSPHERE_SMOOTHING: f32 = 1.0;
+// fraction of the render distance where the fog starts, it gets thicker at night
+const DAY_FOG_START: f32 = 0.8;
+const NIGHT_FOG_START: f32 = 0.5;
+const INITIAL_WATER_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 0.6);
+
+struct MeshBuffers {
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
+    index_format: wgpu::IndexFormat,
     num_indices: u32,
+}
+
+struct WorldStats {
+    chunks_loaded: usize,
+    deleted_blocks: usize,
+    world_bytes: u64,
+    mesh_bytes: u64,
+}
+
+pub struct ChunkRenderData {
+    opaque: Option<MeshBuffers>,
+    transparent: Option<MeshBuffers>,
+    water: Option<MeshBuffers>,
     aabb: AABB,
 }
 
+impl ChunkRenderData {
+    fn buffer_bytes(&self) -> u64 {
+        [&self.opaque, &self.transparent, &self.water]
+            .into_iter()
+            .flatten()
+            .map(|mesh| mesh.vertex_buffer.size() + mesh.index_buffer.size())
+            .sum()
+    }
+
+    fn release(self, buffer_pool: &mut BufferPool) {
+        for mesh in [self.opaque, self.transparent, self.water]
+            .into_iter()
+            .flatten()
+        {
+            buffer_pool.release(mesh.vertex_buffer);
+            buffer_pool.release(mesh.index_buffer);
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 struct CrosshairUniform {
@@ -41,6 +136,436 @@ struct CrosshairUniform {
     _pad: [u8; 4],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct FrameGraphUniform {
+    origin: [f32; 2],
+    screen_size: [f32; 2],
+    frame_times: [[f32; 4]; FRAME_GRAPH_LEN / 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct FogUniform {
+    fog_color: [f32; 3],
+    fog_start: f32,
+    sky_color: [f32; 3],
+    fog_end: f32,
+}
+
+impl FogUniform {
+    fn new(render_distance: f32, fog_color: Vec3, sky_color: Vec3, daylight: f32) -> Self {
+        let fog_end = render_distance * CHUNK_WIDTH as f32;
+        Self {
+            fog_color: fog_color.to_array(),
+            fog_start: lerp(NIGHT_FOG_START, DAY_FOG_START, daylight) * fog_end,
+            sky_color: sky_color.to_array(),
+            fog_end,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyUniform {
+    sun_direction: [f32; 3],
+    time: f32, // fraction of the day, 0 at midnight
+    horizon_color: [f32; 4],
+    zenith_color: [f32; 4],
+}
+
+impl SkyUniform {
+    fn new(world: &World) -> Self {
+        let sun_direction = world.sun_direction();
+        let zenith_color = NIGHT_ZENITH_COLOR.lerp(DAY_ZENITH_COLOR, daylight(sun_direction.z));
+        Self {
+            sun_direction: sun_direction.to_array(),
+            time: world.time / DAY_LENGTH,
+            horizon_color: horizon_color(sun_direction.z).extend(1.0).to_array(),
+            zenith_color: zenith_color.extend(1.0).to_array(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct WaterUniform {
+    color: [f32; 4],
+    time: f32, // seconds, moves the ripples
+    _padding: [f32; 3],
+}
+
+// 0 at night, 1 during the day
+fn daylight(sun_height: f32) -> f32 {
+    let (twilight_start, twilight_end) = TWILIGHT;
+    smoothstep(
+        0.0,
+        1.0,
+        inverse_lerp(twilight_start, twilight_end, sun_height),
+    )
+}
+
+fn horizon_color(sun_height: f32) -> Vec3 {
+    let sunset = (1.0 - sun_height.abs() / SUNSET_HEIGHT).clamp(0.0, 1.0);
+    NIGHT_HORIZON_COLOR
+        .lerp(DAY_HORIZON_COLOR, daylight(sun_height))
+        .lerp(SUNSET_HORIZON_COLOR, sunset)
+}
+
+// $XDG_CACHE_HOME/ft_vox or ~/.cache/ft_vox, unlike the temp dir other users can't write there
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".cache")))?;
+    let dir = base.join("ft_vox");
+    if let Err(err) = fs::create_dir_all(&dir) {
+        log::warn!("failed to create cache directory {}: {err}", dir.display());
+        return None;
+    }
+    Some(dir)
+}
+
+// compiled pipelines only stay valid for the same driver and the same shaders
+fn pipeline_cache_path(adapter_info: &wgpu::AdapterInfo) -> Option<PathBuf> {
+    let key = wgpu::util::pipeline_cache_key(adapter_info)?;
+    let mut hasher = DefaultHasher::new();
+    for kind in ShaderKind::ALL {
+        kind.embedded_source().hash(&mut hasher);
+    }
+    Some(cache_dir()?.join(format!("pipelines_{key}_{:016x}.bin", hasher.finish())))
+}
+
+fn create_shader(device: &wgpu::Device, kind: ShaderKind, source: &str) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(kind.file_name()),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+fn is_error_scope_clean(error_scope: wgpu::ErrorScopeGuard, kind: ShaderKind) -> bool {
+    let Some(err) = pollster::block_on(error_scope.pop()) else {
+        log::info!("reloaded {}", kind.file_name());
+        return true;
+    };
+    log::error!("failed to reload {}: {err}", kind.file_name());
+    false
+}
+
+fn create_voxels_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    cache: Option<&wgpu::PipelineCache>,
+) -> (
+    wgpu::RenderPipeline,
+    wgpu::RenderPipeline,
+    wgpu::RenderPipeline,
+    Option<wgpu::RenderPipeline>,
+) {
+    let create_voxels_pipeline = |label: &str,
+                                  fragment_entry_point: &str,
+                                  blend: wgpu::BlendState,
+                                  depth_write_enabled: bool,
+                                  cull_mode: Option<wgpu::Face>,
+                                  polygon_mode: wgpu::PolygonMode,
+                                  bias: wgpu::DepthBiasState| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Vertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(fragment_entry_point),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode,
+                polygon_mode,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias,
+            }),
+            multisample: wgpu::MultisampleState {
+                count: Texture::MSAA_SAMPLE_COUNT,
+                ..Default::default()
+            },
+            multiview_mask: None,
+            cache,
+        })
+    };
+    let voxels_pipeline = create_voxels_pipeline(
+        "voxels_pipeline",
+        "fs_main",
+        wgpu::BlendState::REPLACE,
+        true,
+        Some(wgpu::Face::Back),
+        wgpu::PolygonMode::Fill,
+        wgpu::DepthBiasState::default(),
+    );
+    // transparent faces are visible from both sides and must not hide each other
+    let create_blended_pipeline = |label: &str, fragment_entry_point: &str| {
+        create_voxels_pipeline(
+            label,
+            fragment_entry_point,
+            wgpu::BlendState::ALPHA_BLENDING,
+            false,
+            None,
+            wgpu::PolygonMode::Fill,
+            wgpu::DepthBiasState::default(),
+        )
+    };
+    let transparent_pipeline = create_blended_pipeline("transparent_pipeline", "fs_transparent");
+    let water_pipeline = create_blended_pipeline("water_pipeline", "fs_water");
+    // pulled towards the camera so the lines win the depth test against their own faces
+    let wireframe_pipeline = device
+        .features()
+        .contains(wgpu::Features::POLYGON_MODE_LINE)
+        .then(|| {
+            create_voxels_pipeline(
+                "wireframe_pipeline",
+                "fs_wireframe",
+                wgpu::BlendState::REPLACE,
+                false,
+                None,
+                wgpu::PolygonMode::Line,
+                wgpu::DepthBiasState {
+                    constant: -2,
+                    slope_scale: -1.0,
+                    clamp: 0.0,
+                },
+            )
+        });
+
+    (
+        voxels_pipeline,
+        transparent_pipeline,
+        water_pipeline,
+        wireframe_pipeline,
+    )
+}
+
+fn create_skybox_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    cache: Option<&wgpu::PipelineCache>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("skybox_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[], // fullscreen triangle: no vertex buffers needed.
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None, // infinite depth
+        multisample: wgpu::MultisampleState {
+            count: Texture::MSAA_SAMPLE_COUNT,
+            ..Default::default()
+        },
+        multiview_mask: None,
+        cache,
+    })
+}
+
+fn create_dof_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    cache: Option<&wgpu::PipelineCache>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("dof_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[], // fullscreen triangle
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(), // reads the resolved scene
+        multiview_mask: None,
+        cache,
+    })
+}
+
+// the scene and depth textures are recreated on resize, and the bind group with them
+fn create_dof_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    scene_view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    depth_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("dof_bind_group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(scene_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(depth_view),
+            },
+        ],
+    })
+}
+
+fn create_crosshair_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    cache: Option<&wgpu::PipelineCache>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("crosshair_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[], // fullscreen triangle
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,                            // overlay = no depth
+        multisample: wgpu::MultisampleState::default(), // drawn after the resolve, like the text
+        multiview_mask: None,
+        cache,
+    })
+}
+
+fn create_minimap_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    cache: Option<&wgpu::PipelineCache>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("minimap_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[], // fullscreen triangle
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache,
+    })
+}
+
+fn create_frame_graph_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    cache: Option<&wgpu::PipelineCache>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("frame_graph_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[], // one quad per instance
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache,
+    })
+}
+
 pub struct State {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
@@ -50,35 +575,99 @@ pub struct State {
     pub size: PhysicalSize<u32>,
     pub center: PhysicalSize<u32>,
     pub fps: f32,
+    pub biome: BiomeType,
     pub show_fps: bool,
+    pub show_debug: bool,
+    debug_noise_values: Option<NoiseValues>,
+    debug_world_stats: Option<WorldStats>,
+    gpu_timer: Option<GpuTimer>,
     pub is_right_clicking: bool,
     pub is_crosshair_active: bool,
+    pub targeted_block: Option<BlockType>,
+    pub selected_block: BlockType,
+    hotbar_scroll: f32,
+    greedy_mesh: bool,
+    // spreads the meshing over several frames when entering a new chunk
+    max_chunks_per_frame: usize,
+    pub render_distance: f32,
 
     pub chunk_render_data: HashMap<ChunkCoords, ChunkRenderData>,
+    buffer_pool: BufferPool,
     pub chunks_to_rerender: HashSet<ChunkCoords>,
+    // sorted from farthest to nearest so that `pop` returns the nearest chunk
+    chunks_to_mesh: Vec<ChunkCoords>,
+    // the chunks in range from nearest to farthest, sorted again when the camera changes chunk
+    draw_order: Vec<ChunkCoords>,
 
     pub camera: Camera,
     pub camera_controller: CameraController,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
 
+    fog_buffer: wgpu::Buffer,
+    fog_bind_group: wgpu::BindGroup,
+    fog_color: Vec3,
+    sky_color: Vec3,
+    daylight: f32,
+
+    water_buffer: wgpu::Buffer,
+    water_bind_group: wgpu::BindGroup,
+    water_color: Vec4,
+
     depth_texture: Texture,
+    msaa_view: wgpu::TextureView,
     diffuse_bind_group: wgpu::BindGroup,
+    pipeline_cache: Option<wgpu::PipelineCache>,
+    voxels_pipeline_layout: wgpu::PipelineLayout,
     voxels_pipeline: wgpu::RenderPipeline,
+    transparent_pipeline: wgpu::RenderPipeline,
+    water_pipeline: wgpu::RenderPipeline,
+    wireframe_pipeline: Option<wgpu::RenderPipeline>, // needs POLYGON_MODE_LINE
+    show_wireframe: bool,
 
+    skybox_pipeline_layout: wgpu::PipelineLayout,
     skybox_pipeline: wgpu::RenderPipeline,
     skybox_bind_group: wgpu::BindGroup,
+    sky_buffer: wgpu::Buffer,
+    sky_bind_group: wgpu::BindGroup,
 
     text_brush: TextBrush<FontRef<'static>>,
 
+    crosshair_pipeline_layout: wgpu::PipelineLayout,
     crosshair_pipeline: wgpu::RenderPipeline,
     crosshair_bind_group: wgpu::BindGroup,
     crosshair_buffer: wgpu::Buffer,
+
+    show_frame_graph: bool,
+    frame_graph_pipeline_layout: wgpu::PipelineLayout,
+    frame_graph_pipeline: wgpu::RenderPipeline,
+    frame_graph_bind_group: wgpu::BindGroup,
+    frame_graph_buffer: wgpu::Buffer,
+    frame_graph_count: u32,
+    worst_frame_time: f32,
+
+    show_minimap: bool,
+    minimap_texture: wgpu::Texture,
+    minimap_bind_group: wgpu::BindGroup,
+    minimap_pipeline_layout: wgpu::PipelineLayout,
+    minimap_pipeline: wgpu::RenderPipeline,
+    // the chunk the minimap was last drawn around
+    minimap_center: Option<ChunkCoords>,
+    minimap_colors: Vec<[u8; 3]>,
+
+    scene_view: wgpu::TextureView,
+    dof_sampler: wgpu::Sampler,
+    dof_bind_group_layout: wgpu::BindGroupLayout,
+    dof_bind_group: wgpu::BindGroup,
+    dof_pipeline_layout: wgpu::PipelineLayout,
+    dof_pipeline: wgpu::RenderPipeline,
+    show_depth_of_field: bool,
+    focus_distance: f32, // distance to the targeted block, or the far plane
 }
 
 impl State {
     #[expect(clippy::too_many_lines)]
-    pub async fn new(window: Arc<Window>, args: &Args) -> Self {
+    pub async fn new(window: Arc<Window>, args: &Args, spawn_point: Vec3) -> Self {
         let size = window.inner_size();
         let center = PhysicalSize::new(size.width / 2, size.height / 2);
 
@@ -110,7 +699,10 @@ impl State {
 
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
+                required_features: adapter.features()
+                    & (wgpu::Features::POLYGON_MODE_LINE
+                        | wgpu::Features::PIPELINE_CACHE
+                        | wgpu::Features::TIMESTAMP_QUERY),
                 required_limits: wgpu::Limits::default(),
                 label: None,
                 memory_hints: wgpu::MemoryHints::default(),
@@ -160,6 +752,7 @@ impl State {
         )
         .unwrap();
         let depth_texture = Texture::create_depth_texture(&device, &config);
+        let msaa_view = Texture::create_msaa_texture(&device, &config);
 
         let texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -201,17 +794,17 @@ impl State {
 
         // === CAMERA ===
         let camera = Camera::new(
-            Vec3::new(0.0, 0.0, 160.0),
+            spawn_point,
             Vec3::new(0.0, 0.0, 1.0),
             config.width as f32 / config.height as f32,
-            (80f32).to_radians(),
+            args.fov.to_radians(),
             CAMERA_NEAR,
-            camera_far(),
+            camera_far(args.render_distance),
         );
 
         let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("camera_buffer"),
-            contents: bytemuck::bytes_of(&CameraUniform::new(&camera)),
+            contents: bytemuck::bytes_of(&CameraUniform::new(&camera, camera.far())),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
@@ -241,55 +834,118 @@ impl State {
 
         let camera_controller = CameraController::new(args);
 
-        // === VOXELS ===
-        let voxels_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../shaders/voxels.wgsl"));
-        let voxels_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("voxels_pipeline"),
-            layout: Some(
-                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("voxels_pipeline_layout"),
-                    bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
-                    immediate_size: 0,
-                }),
-            ),
-            vertex: wgpu::VertexState {
-                module: &voxels_shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &voxels_shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: Texture::DEPTH_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None,
+        // === FOG ===
+        let fog_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("fog_buffer"),
+            contents: bytemuck::bytes_of(&FogUniform::new(
+                args.render_distance,
+                INITIAL_ATMOSPHERE_COLOR,
+                INITIAL_ATMOSPHERE_COLOR,
+                1.0,
+            )),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let fog_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("fog_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let fog_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fog_bind_group"),
+            layout: &fog_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: fog_buffer.as_entire_binding(),
+            }],
+        });
+
+        let water_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("water_uniform"),
+            size: size_of::<WaterUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let water_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("water_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let water_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("water_bind_group"),
+            layout: &water_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: water_buffer.as_entire_binding(),
+            }],
+        });
+
+        // === PIPELINE CACHE ===
+        let pipeline_cache_path = device
+            .features()
+            .contains(wgpu::Features::PIPELINE_CACHE)
+            .then(|| pipeline_cache_path(&adapter.get_info()))
+            .flatten();
+        let pipeline_cache = pipeline_cache_path.as_ref().map(|path| {
+            let data = fs::read(path).ok();
+            // SAFETY: the file is in the user's own cache directory, it is only ever written with
+            // `get_data` and its name contains the cache key and the hash of the shaders
+            unsafe {
+                device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                    label: Some("pipeline_cache"),
+                    data: data.as_deref(),
+                    fallback: true,
+                })
+            }
         });
 
+        // === VOXELS ===
+        let voxels_shader = create_shader(
+            &device,
+            ShaderKind::Voxels,
+            ShaderKind::Voxels.embedded_source(),
+        );
+        let voxels_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("voxels_pipeline_layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &camera_bind_group_layout,
+                    &fog_bind_group_layout,
+                    &water_bind_group_layout,
+                ],
+                immediate_size: 0,
+            });
+        let (voxels_pipeline, transparent_pipeline, water_pipeline, wireframe_pipeline) =
+            create_voxels_pipelines(
+                &device,
+                &voxels_pipeline_layout,
+                &voxels_shader,
+                config.format,
+                pipeline_cache.as_ref(),
+            );
+
         // === SKYBOX ===
         #[expect(clippy::large_include_file)] // FIXME
         let skybox_texture = Texture::from_bytes(
@@ -315,40 +971,60 @@ impl State {
             label: Some("skybox_bind_group"),
         });
 
-        let skybox_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../shaders/skybox.wgsl"));
-        let skybox_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("skybox_pipeline"),
-            layout: Some(
-                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("skybox_pipeline_layout"),
-                    bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
-                    immediate_size: 0,
-                }),
-            ),
-            vertex: wgpu::VertexState {
-                module: &skybox_shader,
-                entry_point: Some("vs_main"),
-                buffers: &[], // fullscreen triangle: no vertex buffers needed.
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &skybox_shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None, // infinite depth
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None,
+        let sky_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sky_uniform"),
+            size: size_of::<SkyUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sky_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sky_bgl"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let sky_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sky_bg"),
+            layout: &sky_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: sky_buffer.as_entire_binding(),
+            }],
         });
 
+        let skybox_shader = create_shader(
+            &device,
+            ShaderKind::Skybox,
+            ShaderKind::Skybox.embedded_source(),
+        );
+        let skybox_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("skybox_pipeline_layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &camera_bind_group_layout,
+                    &fog_bind_group_layout,
+                    &sky_bgl,
+                ],
+                immediate_size: 0,
+            });
+        let skybox_pipeline = create_skybox_pipeline(
+            &device,
+            &skybox_pipeline_layout,
+            &skybox_shader,
+            config.format,
+            pipeline_cache.as_ref(),
+        );
+
         // === CROSSHAIR ===
         let crosshair_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("crosshair_uniform"),
@@ -383,74 +1059,299 @@ impl State {
             }],
         });
 
-        let crosshair_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../shaders/crosshair.wgsl"));
+        let crosshair_shader = create_shader(
+            &device,
+            ShaderKind::Crosshair,
+            ShaderKind::Crosshair.embedded_source(),
+        );
+        let crosshair_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("crosshair_pipeline_layout"),
+                bind_group_layouts: &[&crosshair_bgl],
+                immediate_size: 0,
+            });
+        let crosshair_pipeline = create_crosshair_pipeline(
+            &device,
+            &crosshair_pipeline_layout,
+            &crosshair_shader,
+            config.format,
+            pipeline_cache.as_ref(),
+        );
 
-        let crosshair_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("crosshair_pipeline"),
-            layout: Some(
-                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("crosshair_pipeline_layout"),
-                    bind_group_layouts: &[&crosshair_bgl],
-                    immediate_size: 0,
-                }),
-            ),
-            vertex: wgpu::VertexState {
-                module: &crosshair_shader,
-                entry_point: Some("vs_main"),
-                buffers: &[], // fullscreen triangle
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &crosshair_shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None, // overlay = no depth
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None,
+        // === FRAME GRAPH ===
+        let frame_graph_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame_graph_uniform"),
+            size: size_of::<FrameGraphUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
-        // === FPS ===
-        let text_brush =
-            BrushBuilder::using_font_bytes(include_bytes!("../assets/EP-Boxi-Bold.otf"))
-                .unwrap()
-                .with_depth_stencil(None)
-                .build(&device, config.width, config.height, config.format);
+        let frame_graph_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("frame_graph_bgl"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
 
-        Self {
-            surface,
-            device,
-            queue,
-            config,
+        let frame_graph_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("frame_graph_bg"),
+            layout: &frame_graph_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: frame_graph_buffer.as_entire_binding(),
+            }],
+        });
+
+        let frame_graph_shader = create_shader(
+            &device,
+            ShaderKind::FrameGraph,
+            ShaderKind::FrameGraph.embedded_source(),
+        );
+        let frame_graph_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("frame_graph_pipeline_layout"),
+                bind_group_layouts: &[&frame_graph_bgl],
+                immediate_size: 0,
+            });
+        let frame_graph_pipeline = create_frame_graph_pipeline(
+            &device,
+            &frame_graph_pipeline_layout,
+            &frame_graph_shader,
+            config.format,
+            pipeline_cache.as_ref(),
+        );
+
+        // === MINIMAP ===
+        let minimap_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("minimap_texture"),
+            size: wgpu::Extent3d {
+                width: MINIMAP_SIZE,
+                height: MINIMAP_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let minimap_view = minimap_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let minimap_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let minimap_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&minimap_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&minimap_sampler),
+                },
+            ],
+            label: Some("minimap_bind_group"),
+        });
+
+        let minimap_shader = create_shader(
+            &device,
+            ShaderKind::Minimap,
+            ShaderKind::Minimap.embedded_source(),
+        );
+        let minimap_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("minimap_pipeline_layout"),
+                bind_group_layouts: &[&texture_bind_group_layout],
+                immediate_size: 0,
+            });
+        let minimap_pipeline = create_minimap_pipeline(
+            &device,
+            &minimap_pipeline_layout,
+            &minimap_shader,
+            config.format,
+            pipeline_cache.as_ref(),
+        );
+
+        // === DEPTH OF FIELD ===
+        let scene_view = Texture::create_scene_texture(&device, &config);
+        let dof_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("dof_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let dof_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("dof_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: true,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let dof_bind_group = create_dof_bind_group(
+            &device,
+            &dof_bind_group_layout,
+            &scene_view,
+            &dof_sampler,
+            &depth_texture.view,
+        );
+
+        let dof_shader = create_shader(
+            &device,
+            ShaderKind::DepthOfField,
+            ShaderKind::DepthOfField.embedded_source(),
+        );
+        let dof_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("dof_pipeline_layout"),
+            bind_group_layouts: &[&dof_bind_group_layout, &camera_bind_group_layout],
+            immediate_size: 0,
+        });
+        let dof_pipeline = create_dof_pipeline(
+            &device,
+            &dof_pipeline_layout,
+            &dof_shader,
+            config.format,
+            pipeline_cache.as_ref(),
+        );
+
+        if let (Some(cache), Some(path)) = (&pipeline_cache, &pipeline_cache_path)
+            && let Some(data) = cache.get_data()
+            && let Err(err) = fs::write(path, data)
+        {
+            log::warn!("failed to write pipeline cache {}: {err}", path.display());
+        }
+
+        // === FPS ===
+        let text_brush =
+            BrushBuilder::using_font_bytes(include_bytes!("../assets/EP-Boxi-Bold.otf"))
+                .unwrap()
+                .with_depth_stencil(None)
+                .build(&device, config.width, config.height, config.format);
+
+        // === GPU TIMER ===
+        let gpu_timer = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| GpuTimer::new(&device, &queue));
+
+        Self {
+            surface,
+            device,
+            queue,
+            config,
             size,
             center,
+            pipeline_cache,
+            voxels_pipeline_layout,
             voxels_pipeline,
+            transparent_pipeline,
+            water_pipeline,
+            wireframe_pipeline,
+            show_wireframe: false,
             chunk_render_data: HashMap::new(),
+            buffer_pool: BufferPool::default(),
             chunks_to_rerender: HashSet::new(),
+            chunks_to_mesh: Vec::new(),
+            draw_order: Vec::new(),
             diffuse_bind_group,
             depth_texture,
+            msaa_view,
             camera,
             camera_buffer,
             camera_bind_group,
             camera_controller,
+            fog_buffer,
+            fog_bind_group,
+            fog_color: INITIAL_ATMOSPHERE_COLOR,
+            sky_color: INITIAL_ATMOSPHERE_COLOR,
+            daylight: 1.0,
+            skybox_pipeline_layout,
             skybox_pipeline,
             skybox_bind_group,
-            fps: 60.0, // dummy value before first calculation
+            sky_buffer,
+            sky_bind_group,
+            water_buffer,
+            water_bind_group,
+            water_color: INITIAL_WATER_COLOR,
+            fps: 60.0,                // dummy value before first calculation
+            biome: BiomeType::Plains, // same
             show_fps: false,
+            show_debug: false,
+            debug_noise_values: None,
+            debug_world_stats: None,
+            gpu_timer,
             text_brush,
             is_right_clicking: false,
             is_crosshair_active: false,
+            targeted_block: None,
+            selected_block: HOTBAR[0],
+            hotbar_scroll: 0.0,
+            greedy_mesh: args.greedy_mesh,
+            max_chunks_per_frame: args.max_chunks_per_frame.max(1), // 0 would never mesh anything
+            render_distance: args.render_distance,
+            crosshair_pipeline_layout,
             crosshair_pipeline,
             crosshair_bind_group,
             crosshair_buffer,
+            show_frame_graph: false,
+            frame_graph_pipeline_layout,
+            frame_graph_pipeline,
+            frame_graph_bind_group,
+            frame_graph_buffer,
+            frame_graph_count: 0,
+            worst_frame_time: 0.0,
+            show_minimap: false,
+            minimap_texture,
+            minimap_bind_group,
+            minimap_pipeline_layout,
+            minimap_pipeline,
+            minimap_center: None,
+            minimap_colors: Vec::new(),
+            scene_view,
+            dof_sampler,
+            dof_bind_group_layout,
+            dof_bind_group,
+            dof_pipeline_layout,
+            dof_pipeline,
+            show_depth_of_field: false,
+            focus_distance: camera_far(args.render_distance),
         }
     }
 
@@ -472,38 +1373,140 @@ impl State {
             .resize_view(new_size.width as f32, new_size.height as f32, &self.queue);
 
         self.depth_texture = Texture::create_depth_texture(&self.device, &self.config);
+        self.msaa_view = Texture::create_msaa_texture(&self.device, &self.config);
+        self.scene_view = Texture::create_scene_texture(&self.device, &self.config);
+        self.dof_bind_group = create_dof_bind_group(
+            &self.device,
+            &self.dof_bind_group_layout,
+            &self.scene_view,
+            &self.dof_sampler,
+            &self.depth_texture.view,
+        );
     }
 
     pub fn update_chunks(&mut self, world: &mut World) {
-        let (chunk_x, chunk_y) = camera_to_chunk_coords(self.camera.position());
+        let camera_chunk = camera_to_chunk_coords(self.camera.position());
 
-        let render_distance = RENDER_DISTANCE.floor() as i32;
-        let render_distance_sq = RENDER_DISTANCE * RENDER_DISTANCE;
+        let mut chunks_in_range: Vec<_> =
+            chunk_neighborhood(camera_chunk, self.render_distance).collect();
 
-        let mut chunks_in_range = HashSet::new();
+        // the nearest chunks are requested first so they are generated first
+        chunks_in_range
+            .sort_by_key(|&chunk_coords| chunk_distance_squared(camera_chunk, chunk_coords));
+        for &chunk_coords in &chunks_in_range {
+            world.request_neighborhood(chunk_coords);
+        }
 
-        for dy in -render_distance..=render_distance {
-            let dy_sq = dy * dy;
-            let max_dx_sq = render_distance_sq - dy_sq as f32;
-            let max_dx = max_dx_sq.sqrt() as i32;
+        self.draw_order.clone_from(&chunks_in_range);
 
-            for dx in -max_dx..=max_dx {
-                let chunk_coords = (chunk_x + dx, chunk_y + dy);
-                chunks_in_range.insert(chunk_coords);
-                world.load_chunk(chunk_coords);
-            }
+        let chunks_in_range: HashSet<ChunkCoords> = chunks_in_range.into_iter().collect();
+        for (_, render_data) in self
+            .chunk_render_data
+            .extract_if(|coords, _| !chunks_in_range.contains(coords))
+        {
+            render_data.release(&mut self.buffer_pool);
         }
 
-        self.chunk_render_data
-            .retain(|&coords, _| chunks_in_range.contains(&coords));
+        // the other chunks get meshed once generated, see `receive_generated_chunks`
+        self.chunks_to_mesh = chunks_in_range
+            .into_iter()
+            .filter(|chunk_coords| {
+                !self.chunk_render_data.contains_key(chunk_coords)
+                    && world.is_neighborhood_loaded(*chunk_coords)
+            })
+            .collect();
+        self.chunks_to_mesh.sort_by_key(|&chunk_coords| {
+            Reverse(chunk_distance_squared(camera_chunk, chunk_coords))
+        });
+    }
 
-        for &chunk_coords in &chunks_in_range {
-            if !self.chunk_render_data.contains_key(&chunk_coords) {
+    fn mesh_queued_chunks(&mut self, world: &mut World) {
+        let mut meshed = 0;
+        while meshed < self.max_chunks_per_frame
+            && let Some(chunk_coords) = self.chunks_to_mesh.pop()
+        {
+            // the chunk may have been meshed in the meantime, e.g. by the preloading in app.rs
+            if !self.chunk_render_data.contains_key(&chunk_coords)
+                && world.is_neighborhood_loaded(chunk_coords)
+            {
                 self.generate_chunk_mesh(world, chunk_coords);
+                meshed += 1;
             }
         }
     }
 
+    // queues the freshly generated chunks along with the neighbors that were waiting on them,
+    // `mesh_queued_chunks` meshes them within the per-frame budget
+    fn receive_generated_chunks(&mut self, world: &mut World) {
+        let camera_chunk = camera_to_chunk_coords(self.camera.position());
+        let mut received = false;
+        for generated_chunk in world.receive_generated_chunks() {
+            received = true;
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let chunk_coords = generated_chunk.offset(dx, dy);
+                    if chunk_distance(camera_chunk, chunk_coords) < self.render_distance
+                        && !self.chunk_render_data.contains_key(&chunk_coords)
+                        && world.is_neighborhood_loaded(chunk_coords)
+                    {
+                        self.chunks_to_mesh.push(chunk_coords);
+                    }
+                }
+            }
+        }
+        if received {
+            // the coordinates break the ties so that duplicates end up next to each other
+            self.chunks_to_mesh.sort_by_key(|&chunk_coords| {
+                (
+                    Reverse(chunk_distance_squared(camera_chunk, chunk_coords)),
+                    chunk_coords.cx,
+                    chunk_coords.cy,
+                )
+            });
+            self.chunks_to_mesh.dedup();
+        }
+    }
+
+    pub fn increase_render_distance(&mut self, world: &mut World) {
+        self.set_render_distance(world, self.render_distance + RENDER_DISTANCE_STEP);
+    }
+
+    pub fn decrease_render_distance(&mut self, world: &mut World) {
+        self.set_render_distance(world, self.render_distance - RENDER_DISTANCE_STEP);
+    }
+
+    fn set_render_distance(&mut self, world: &mut World, render_distance: f32) {
+        self.render_distance = render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+        log::info!("Render distance: {}", self.render_distance);
+        self.camera.set_far(camera_far(self.render_distance));
+        self.write_fog_buffer();
+        self.update_chunks(world);
+    }
+
+    // refreshed when entering a new chunk, so the position lags a bit behind
+    pub fn format_window_title(seed: u64, camera_pos: Vec3, biome: BiomeType) -> String {
+        format!(
+            "ft_vox | Seed: {seed} | ({:.0}, {:.0}, {:.0}) | {}",
+            camera_pos.x,
+            camera_pos.y,
+            camera_pos.z,
+            biome.display_name(),
+        )
+    }
+
+    pub fn undo_last_deletion(&mut self, world: &mut World) {
+        if let Some((world_coords, block)) = world.undo_last_deletion() {
+            log::debug!("Restored {block:?}");
+            self.schedule_rerender(world_coords);
+            self.update_crosshair(world);
+        }
+    }
+
+    pub fn schedule_rerender(&mut self, world_coords: WorldCoords) {
+        self.chunks_to_rerender
+            .extend(affected_chunks(world_coords));
+    }
+
     pub fn rerender_chunks(&mut self, world: &mut World) {
         for chunk_coords in std::mem::take(&mut self.chunks_to_rerender) {
             self.generate_chunk_mesh(world, chunk_coords);
@@ -512,57 +1515,140 @@ impl State {
 
     pub fn generate_chunk_mesh(&mut self, world: &mut World, chunk_coords: ChunkCoords) {
         let camera_chunk = camera_to_chunk_coords(self.camera.position());
-        let (chunk_x, chunk_y) = chunk_coords;
-        let (mut vertices, indices) = world.generate_chunk_mesh(chunk_coords, camera_chunk);
-        if vertices.is_empty() || indices.is_empty() {
+        let ChunkMesh {
+            opaque,
+            transparent,
+            water,
+        } = world.generate_chunk_mesh(
+            chunk_coords,
+            camera_chunk,
+            self.render_distance,
+            self.greedy_mesh,
+        );
+        // released first so that a mesh of a similar size gets the same buffers back
+        if let Some(previous) = self.chunk_render_data.remove(&chunk_coords) {
+            previous.release(&mut self.buffer_pool);
+        }
+        // a rerendered chunk may have lost its last visible face
+        if opaque.is_empty() && transparent.is_empty() && water.is_empty() {
             return;
         }
 
+        let chunk = world.get_chunk_if_loaded(chunk_coords).unwrap();
+        let aabb = chunk.bounding_box();
+
+        let render_data = ChunkRenderData {
+            opaque: self.create_mesh_buffers(chunk_coords, opaque, "Opaque"),
+            transparent: self.create_mesh_buffers(chunk_coords, transparent, "Transparent"),
+            water: self.create_mesh_buffers(chunk_coords, water, "Water"),
+            aabb,
+        };
+
+        self.chunk_render_data.insert(chunk_coords, render_data);
+    }
+
+    fn create_mesh_buffers(
+        &mut self,
+        ChunkCoords {
+            cx: chunk_x,
+            cy: chunk_y,
+        }: ChunkCoords,
+        mut mesh: Mesh,
+        label: &str,
+    ) -> Option<MeshBuffers> {
+        if mesh.is_empty() {
+            return None;
+        }
+
         let world_offset_x = chunk_x as f32 * CHUNK_WIDTH as f32;
         let world_offset_y = chunk_y as f32 * CHUNK_WIDTH as f32;
 
-        for vertex in &mut vertices {
+        for vertex in &mut mesh.vertices {
             vertex.position[0] += world_offset_x;
             vertex.position[1] += world_offset_y;
         }
 
-        let vertex_buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some(&format!("Chunk ({chunk_x}, {chunk_y}) Vertex Buffer")),
-                contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
+        let vertex_buffer = self.buffer_pool.upload(
+            &self.device,
+            &self.queue,
+            &format!("Chunk {label} Vertex Buffer"),
+            wgpu::BufferUsages::VERTEX,
+            bytemuck::cast_slice(&mesh.vertices),
+        );
 
-        let index_buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some(&format!("Chunk ({chunk_x}, {chunk_y}) Index Buffer")),
-                contents: bytemuck::cast_slice(&indices),
-                usage: wgpu::BufferUsages::INDEX,
-            });
+        // 16-bit indices are enough unless the mesh has more vertices than they can address
+        let mut short_indices: Vec<u16>;
+        let (index_format, index_contents) = if mesh.vertices.len() > usize::from(u16::MAX) + 1 {
+            (
+                wgpu::IndexFormat::Uint32,
+                bytemuck::cast_slice(&mesh.indices),
+            )
+        } else {
+            short_indices = mesh.indices.iter().map(|&i| i as u16).collect();
+            // buffer writes must be a multiple of 4 bytes, the padding is never drawn
+            if short_indices.len() % 2 == 1 {
+                short_indices.push(0);
+            }
+            (
+                wgpu::IndexFormat::Uint16,
+                bytemuck::cast_slice(&short_indices),
+            )
+        };
 
-        let chunk = world.get_chunk_if_loaded(chunk_coords).unwrap();
-        let aabb = chunk.bounding_box();
+        let index_buffer = self.buffer_pool.upload(
+            &self.device,
+            &self.queue,
+            &format!("Chunk {label} Index Buffer"),
+            wgpu::BufferUsages::INDEX,
+            index_contents,
+        );
 
-        let render_data = ChunkRenderData {
+        Some(MeshBuffers {
             vertex_buffer,
             index_buffer,
-            num_indices: indices.len() as u32,
-            aabb,
-        };
-
-        self.chunk_render_data
-            .insert((chunk_x, chunk_y), render_data);
+            index_format,
+            num_indices: mesh.indices.len() as u32,
+        })
     }
 
-    pub fn update(&mut self, dt: Duration) {
+    pub fn update(&mut self, world: &mut World, dt: Duration) {
+        self.receive_generated_chunks(world);
+        self.mesh_queued_chunks(world);
         self.camera_controller
-            .update(&mut self.camera, dt.as_secs_f32());
+            .update(&mut self.camera, world, dt.as_secs_f32());
+        let target = world.find_block_in_dir(
+            self.camera.position(),
+            self.camera.direction(),
+            MAX_DELETE_DISTANCE,
+        );
+        self.targeted_block = target.map(|(_, _, block, _)| block);
+        self.focus_distance = target.map_or_else(|| camera_far(self.render_distance), |(t, ..)| t);
+        self.debug_noise_values = self.show_debug.then(|| {
+            let column = world.get_column_info_under(self.camera.position());
+            self.biome = column.biome;
+            column.noise
+        });
+        self.debug_world_stats = self.show_debug.then(|| WorldStats {
+            chunks_loaded: world.chunks_loaded(),
+            deleted_blocks: world.deleted_block_count(),
+            world_bytes: world.memory_estimate_bytes() as u64,
+            mesh_bytes: self
+                .chunk_render_data
+                .values()
+                .map(ChunkRenderData::buffer_bytes)
+                .sum(),
+        });
+        self.update_atmosphere(world, dt.as_secs_f32());
+        self.update_minimap(world);
+        self.queue.write_buffer(
+            &self.sky_buffer,
+            0,
+            bytemuck::bytes_of(&SkyUniform::new(world)),
+        );
         self.queue.write_buffer(
             &self.camera_buffer,
             0,
-            bytemuck::bytes_of(&CameraUniform::new(&self.camera)),
+            bytemuck::bytes_of(&CameraUniform::new(&self.camera, self.focus_distance)),
         );
         self.queue.write_buffer(
             &self.crosshair_buffer,
@@ -575,6 +1661,66 @@ impl State {
         );
     }
 
+    // fades toward the colors of the current biome instead of switching at the border
+    // during the day the fog blends into the horizon of the sky instead
+    fn update_atmosphere(&mut self, world: &mut World, dt: f32) {
+        let position = self.camera.position();
+        let t = 1.0 - (-ATMOSPHERE_SMOOTHING * dt).exp();
+        let sun_height = world.sun_direction().z;
+        let fog_color = Vec3::from(world.get_fog_color_at(position))
+            .lerp(horizon_color(sun_height), daylight(sun_height));
+        self.fog_color = self.fog_color.lerp(fog_color, t);
+        self.sky_color = self
+            .sky_color
+            .lerp(Vec3::from(world.get_sky_color_at(position)), t);
+        self.daylight = daylight(sun_height);
+        self.write_fog_buffer();
+
+        self.water_color = self
+            .water_color
+            .lerp(Vec4::from(world.get_water_color_at(position)), t);
+        self.queue.write_buffer(
+            &self.water_buffer,
+            0,
+            bytemuck::bytes_of(&WaterUniform {
+                color: self.water_color.to_array(),
+                time: world.time,
+                _padding: [0.0; 3],
+            }),
+        );
+    }
+
+    fn write_fog_buffer(&self) {
+        self.queue.write_buffer(
+            &self.fog_buffer,
+            0,
+            bytemuck::bytes_of(&FogUniform::new(
+                self.render_distance,
+                self.fog_color,
+                self.sky_color,
+                self.daylight,
+            )),
+        );
+    }
+
+    // touchpads send fractions of lines, so wait for a full one before switching
+    pub fn scroll_hotbar(&mut self, lines: f32) {
+        self.hotbar_scroll += lines;
+        let steps = self.hotbar_scroll.trunc();
+        if steps == 0.0 {
+            return;
+        }
+        self.hotbar_scroll -= steps;
+
+        let index = HOTBAR
+            .iter()
+            .position(|&block| block == self.selected_block)
+            .unwrap_or_default();
+        self.selected_block =
+            HOTBAR[(index as i32 - steps as i32).rem_euclid(HOTBAR.len() as i32) as usize];
+        log::info!("selected {}", self.selected_block.name());
+    }
+
     pub fn update_crosshair(&mut self, world: &World) {
         self.is_crosshair_active = self.is_right_clicking
             && world
@@ -587,16 +1733,25 @@ impl State {
     }
 
     #[expect(clippy::too_many_lines)]
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        fn render_skybox(
+    fn render_scene(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture_view: &wgpu::TextureView,
+    ) {
+        // only measured while the debug overlay shows the durations
+        fn timestamp_writes(
             state: &State,
-            encoder: &mut wgpu::CommandEncoder,
-            texture_view: &wgpu::TextureView,
-        ) {
+            pass: GpuPass,
+        ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+            let gpu_timer = state.gpu_timer.as_ref()?;
+            state.show_debug.then(|| gpu_timer.timestamp_writes(pass))
+        }
+
+        fn render_skybox(state: &State, encoder: &mut wgpu::CommandEncoder) {
             let mut skybox_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("skybox_pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: texture_view,
+                    view: &state.msaa_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -606,13 +1761,15 @@ impl State {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: timestamp_writes(state, GpuPass::Skybox),
                 multiview_mask: None,
             });
 
             skybox_pass.set_pipeline(&state.skybox_pipeline);
             skybox_pass.set_bind_group(0, &state.skybox_bind_group, &[]);
             skybox_pass.set_bind_group(1, &state.camera_bind_group, &[]);
+            skybox_pass.set_bind_group(2, &state.fog_bind_group, &[]);
+            skybox_pass.set_bind_group(3, &state.sky_bind_group, &[]);
             skybox_pass.draw(0..3, 0..1); // fullscreen triangle: 3 vertices, 1 instance.
         }
 
@@ -624,11 +1781,11 @@ impl State {
             let mut voxels_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("voxels_pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: texture_view,
-                    resolve_target: None,
+                    view: &state.msaa_view,
+                    resolve_target: Some(texture_view), // the overlay is drawn on the resolved image
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Load, // load previous color (the skybox)
-                        store: wgpu::StoreOp::Store,
+                        store: wgpu::StoreOp::Discard,
                     },
                     depth_slice: None,
                 })],
@@ -641,30 +1798,102 @@ impl State {
                     stencil_ops: None,
                 }),
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: timestamp_writes(state, GpuPass::Voxels),
                 multiview_mask: None,
             });
 
-            voxels_pass.set_pipeline(&state.voxels_pipeline);
+            fn draw_mesh(pass: &mut wgpu::RenderPass, mesh: &MeshBuffers) {
+                pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+                pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+            }
+
             voxels_pass.set_bind_group(0, &state.diffuse_bind_group, &[]);
             voxels_pass.set_bind_group(1, &state.camera_bind_group, &[]);
+            voxels_pass.set_bind_group(2, &state.fog_bind_group, &[]);
+            voxels_pass.set_bind_group(3, &state.water_bind_group, &[]);
 
             let frustum = state.camera.get_frustum();
             let camera_coords = camera_to_chunk_coords(state.camera.position());
-            for (chunk_coords, render_data) in &state.chunk_render_data {
-                if chunk_distance(camera_coords, *chunk_coords) < RENDER_DISTANCE
-                    && frustum.intersects_aabb(&render_data.aabb)
-                {
-                    voxels_pass.set_vertex_buffer(0, render_data.vertex_buffer.slice(..));
-                    voxels_pass.set_index_buffer(
-                        render_data.index_buffer.slice(..),
-                        wgpu::IndexFormat::Uint16,
-                    );
-                    voxels_pass.draw_indexed(0..render_data.num_indices, 0, 0..1);
+            // opaque meshes are drawn front-to-back so that the depth test rejects hidden fragments
+            let visible_chunks = state
+                .draw_order
+                .iter()
+                .filter_map(|chunk_coords| {
+                    Some((chunk_coords, state.chunk_render_data.get(chunk_coords)?))
+                })
+                .filter(|&(&chunk_coords, render_data)| {
+                    chunk_distance(camera_coords, chunk_coords) < state.render_distance
+                        && frustum.intersects_aabb(&render_data.aabb)
+                })
+                .collect::<Vec<_>>();
+
+            voxels_pass.set_pipeline(&state.voxels_pipeline);
+            for (_, render_data) in &visible_chunks {
+                if let Some(opaque) = &render_data.opaque {
+                    draw_mesh(&mut voxels_pass, opaque);
+                }
+            }
+
+            // transparent meshes are blended back-to-front, chunk by chunk
+            for (_, render_data) in visible_chunks.iter().rev() {
+                if let Some(water) = &render_data.water {
+                    voxels_pass.set_pipeline(&state.water_pipeline);
+                    draw_mesh(&mut voxels_pass, water);
+                }
+                if let Some(transparent) = &render_data.transparent {
+                    voxels_pass.set_pipeline(&state.transparent_pipeline);
+                    draw_mesh(&mut voxels_pass, transparent);
+                }
+            }
+
+            if let Some(wireframe_pipeline) = &state.wireframe_pipeline
+                && state.show_wireframe
+            {
+                voxels_pass.set_pipeline(wireframe_pipeline);
+                for (_, render_data) in &visible_chunks {
+                    for mesh in [
+                        &render_data.opaque,
+                        &render_data.transparent,
+                        &render_data.water,
+                    ]
+                    .into_iter()
+                    .flatten()
+                    {
+                        draw_mesh(&mut voxels_pass, mesh);
+                    }
                 }
             }
         }
 
+        fn render_depth_of_field(
+            state: &State,
+            encoder: &mut wgpu::CommandEncoder,
+            texture_view: &wgpu::TextureView,
+        ) {
+            let mut dof_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("dof_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: texture_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: timestamp_writes(state, GpuPass::DepthOfField),
+                multiview_mask: None,
+            });
+
+            dof_pass.set_pipeline(&state.dof_pipeline);
+            dof_pass.set_bind_group(0, &state.dof_bind_group, &[]);
+            dof_pass.set_bind_group(1, &state.camera_bind_group, &[]);
+            dof_pass.draw(0..3, 0..1);
+        }
+
         fn make_text(text: &str, corner_offset: f32, [r, g, b]: [f32; 3]) -> Section<'_> {
             Section::default()
                 .with_layout(
@@ -676,6 +1905,72 @@ impl State {
                 .add_text(Text::new(text).with_scale(24.0).with_color([r, g, b, 1.0]))
         }
 
+        fn make_column(
+            text: &str,
+            position: (f32, f32),
+            h_align: HorizontalAlign,
+            [r, g, b]: [f32; 3],
+        ) -> Section<'_> {
+            Section::default()
+                .with_layout(
+                    Layout::default()
+                        .h_align(h_align)
+                        .v_align(VerticalAlign::Top),
+                )
+                .with_screen_position(position)
+                .add_text(Text::new(text).with_scale(20.0).with_color([r, g, b, 1.0]))
+        }
+
+        fn debug_columns(state: &State) -> (String, String) {
+            let position = state.camera.position();
+            let WorldCoords { x, y, z } = camera_to_world_coords(position);
+            let ChunkCoords { cx, cy } = camera_to_chunk_coords(position);
+            let gpu_durations = state
+                .gpu_timer
+                .as_ref()
+                .map_or_else(String::new, |gpu_timer| {
+                    GpuPass::ALL
+                        .into_iter()
+                        .filter(|&pass| pass != GpuPass::DepthOfField || state.show_depth_of_field)
+                        .map(|pass| {
+                            format!("\n{}: {:.2} ms", pass.name(), gpu_timer.duration_ms(pass))
+                        })
+                        .collect::<Vec<_>>()
+                        .concat()
+                });
+            let world_stats = state
+                .debug_world_stats
+                .as_ref()
+                .map_or_else(String::new, |stats| {
+                    format!(
+                        "\nLoaded chunks: {}\nDeleted blocks: {}\nMemory: {:.0} MB + {:.0} MB meshes",
+                        stats.chunks_loaded,
+                        stats.deleted_blocks,
+                        stats.world_bytes as f64 / MEGABYTE,
+                        stats.mesh_bytes as f64 / MEGABYTE,
+                    )
+                });
+            let left = format!(
+                "FPS: {:.0}\nXYZ: {x} / {y} / {z}\nChunk: {cx} {cy}\nBiome: {}\nFOV: {:.0} x {:.0}{world_stats}{gpu_durations}",
+                state.fps,
+                state.biome.display_name(),
+                state.camera.fov_x_deg(),
+                state.camera.fov_y_deg(),
+            );
+            let right = state.debug_noise_values.map_or_else(String::new, |values| {
+                format!(
+                    "Temperature: {:.3}\nHumidity: {:.3}\nContinentalness: {:.3}\nErosion: {:.3}\nWeirdness: {:.3}\nPeaks/Valleys: {:.3}",
+                    values.temperature,
+                    values.humidity,
+                    values.continentalness,
+                    values.erosion,
+                    values.weirdness,
+                    values.pv,
+                )
+            });
+            (left, right)
+        }
+
         fn render_overlay(
             state: &mut State,
             encoder: &mut wgpu::CommandEncoder,
@@ -694,24 +1989,90 @@ impl State {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: timestamp_writes(state, GpuPass::Overlay),
                 multiview_mask: None,
             });
 
-            if state.show_fps {
-                let fps_text = format!("FPS:{:.0}", state.fps);
-                let core = make_text(&fps_text, 12.0, [1.0, 0.1, 0.1]);
-                let shadow = make_text(&fps_text, 14.0, [0.0; 3]);
+            let fps_text = format!(
+                "FPS:{:.0}\n{}\nSensitivity: {:.4}\nFacing: {}\nAltitude: {:.1}",
+                state.fps,
+                state.biome.display_name(),
+                state.camera_controller.sensitivity,
+                state.camera.facing_cardinal(),
+                state.camera.altitude(),
+            );
+            let worst_frame_text = format!("Worst: {:.1} ms", state.worst_frame_time);
+            let (debug_left, debug_right) = debug_columns(state);
+            let right_edge = state.size.width as f32;
+            let mut sections = Vec::new();
+            if state.show_debug {
+                for (offset, color) in [(10.0, [0.0; 3]), (8.0, [1.0; 3])] {
+                    sections.push(make_column(
+                        &debug_left,
+                        (offset, offset),
+                        HorizontalAlign::Left,
+                        color,
+                    ));
+                    sections.push(make_column(
+                        &debug_right,
+                        (right_edge - 16.0 + offset, offset),
+                        HorizontalAlign::Right,
+                        color,
+                    ));
+                }
+            } else if state.show_fps {
+                sections.push(make_text(&fps_text, 14.0, [0.0; 3]));
+                sections.push(make_text(&fps_text, 12.0, [1.0, 0.1, 0.1]));
+            }
+            if state.show_frame_graph {
+                let label_y =
+                    state.size.height as f32 - FRAME_GRAPH_MARGIN - FRAME_GRAPH_HEIGHT - 24.0;
+                for (offset, color) in [(2.0, [0.0; 3]), (0.0, [1.0; 3])] {
+                    sections.push(make_column(
+                        &worst_frame_text,
+                        (FRAME_GRAPH_MARGIN + offset, label_y + offset),
+                        HorizontalAlign::Left,
+                        color,
+                    ));
+                }
+            }
+            if let Some(block) = state.targeted_block {
+                // tooltip just below the crosshair
+                sections.push(
+                    Section::default()
+                        .with_layout(
+                            Layout::default()
+                                .h_align(HorizontalAlign::Center)
+                                .v_align(VerticalAlign::Top),
+                        )
+                        .with_screen_position((
+                            state.center.width as f32,
+                            state.center.height as f32 + 16.0,
+                        ))
+                        .add_text(
+                            Text::new(block.name())
+                                .with_scale(16.0)
+                                .with_color([1.0; 4]),
+                        ),
+                );
+            }
+            if !sections.is_empty() {
                 if let Err(brush_error) =
                     state
                         .text_brush
-                        .queue(&state.device, &state.queue, [shadow, core])
+                        .queue(&state.device, &state.queue, sections)
                 {
                     log::warn!("Brush error: {brush_error:?}");
                 }
                 state.text_brush.draw(&mut overlay_pass);
             }
 
+            if state.show_frame_graph {
+                overlay_pass.set_pipeline(&state.frame_graph_pipeline);
+                overlay_pass.set_bind_group(0, &state.frame_graph_bind_group, &[]);
+                overlay_pass.draw(0..6, 0..state.frame_graph_count);
+            }
+
             let arm_length: u32 = 8; // needs to stay bigger than the arm length defined in the shader
             overlay_pass.set_scissor_rect(
                 state.center.width - arm_length,
@@ -723,8 +2084,41 @@ impl State {
             overlay_pass.set_pipeline(&state.crosshair_pipeline);
             overlay_pass.set_bind_group(0, &state.crosshair_bind_group, &[]);
             overlay_pass.draw(0..3, 0..1);
+
+            // bottom right corner, away from the debug screen and the frame graph
+            if state.show_minimap
+                && state.size.width >= MINIMAP_SIZE + MINIMAP_MARGIN
+                && state.size.height >= MINIMAP_SIZE + MINIMAP_MARGIN
+            {
+                let x = state.size.width - MINIMAP_SIZE - MINIMAP_MARGIN;
+                let y = state.size.height - MINIMAP_SIZE - MINIMAP_MARGIN;
+                overlay_pass.set_viewport(
+                    x as f32,
+                    y as f32,
+                    MINIMAP_SIZE as f32,
+                    MINIMAP_SIZE as f32,
+                    0.0,
+                    1.0,
+                );
+                overlay_pass.set_scissor_rect(x, y, MINIMAP_SIZE, MINIMAP_SIZE);
+                overlay_pass.set_pipeline(&state.minimap_pipeline);
+                overlay_pass.set_bind_group(0, &state.minimap_bind_group, &[]);
+                overlay_pass.draw(0..3, 0..1);
+            }
+        }
+
+        render_skybox(self, encoder);
+        if self.show_depth_of_field {
+            // the blur reads the resolved scene, so it can't be resolved into its own target
+            render_voxels(self, encoder, &self.scene_view);
+            render_depth_of_field(self, encoder, texture_view);
+        } else {
+            render_voxels(self, encoder, texture_view);
         }
+        render_overlay(self, encoder, texture_view);
+    }
 
+    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = match self.surface.get_current_texture() {
             Ok(output) => output,
             Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
@@ -743,16 +2137,325 @@ impl State {
                 label: Some("encoder"),
             });
 
-        render_skybox(self, &mut encoder, &texture_view);
-        render_voxels(self, &mut encoder, &texture_view);
-        render_overlay(self, &mut encoder, &texture_view);
+        if self.show_debug
+            && let Some(gpu_timer) = &mut self.gpu_timer
+        {
+            gpu_timer.read_back(&self.device);
+        }
+
+        self.render_scene(&mut encoder, &texture_view);
 
+        let mut gpu_timer = self.gpu_timer.as_mut().filter(|_| self.show_debug);
+        if let Some(gpu_timer) = &mut gpu_timer {
+            gpu_timer.resolve(&mut encoder);
+        }
         self.queue.submit([encoder.finish()]);
+        if let Some(gpu_timer) = gpu_timer {
+            gpu_timer.map();
+        }
         output.present();
         Ok(())
     }
 
+    // renders a frame offscreen and returns it encoded as png
+    pub fn take_screenshot(&mut self) -> Vec<u8> {
+        let (width, height) = (self.config.width, self.config.height);
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("screenshot_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // rows of a texture copy must be aligned to 256 bytes
+        let unpadded_bytes_per_row = 4 * width;
+        let bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("screenshot_buffer"),
+            size: (bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("screenshot_encoder"),
+            });
+        self.render_scene(&mut encoder, &texture_view);
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .unwrap();
+        receiver.recv().unwrap().unwrap();
+
+        let is_bgra = matches!(
+            self.config.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks_exact(bytes_per_row as usize)
+        {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        if is_bgra {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        buffer.unmap();
+
+        let mut png = Vec::new();
+        RgbaImage::from_raw(width, height, pixels)
+            .unwrap()
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    // keeps the current pipelines when the new shader doesn't compile
+    pub fn reload_shader(&mut self, kind: ShaderKind) {
+        let source = match kind.read_source() {
+            Ok(source) => source,
+            Err(err) => {
+                log::error!("failed to read {}: {err}", kind.file_name());
+                return;
+            }
+        };
+
+        let error_scope = self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = create_shader(&self.device, kind, &source);
+        let format = self.config.format;
+        let cache = self.pipeline_cache.as_ref();
+        match kind {
+            ShaderKind::Voxels => {
+                let pipelines = create_voxels_pipelines(
+                    &self.device,
+                    &self.voxels_pipeline_layout,
+                    &shader,
+                    format,
+                    cache,
+                );
+                if is_error_scope_clean(error_scope, kind) {
+                    (
+                        self.voxels_pipeline,
+                        self.transparent_pipeline,
+                        self.water_pipeline,
+                        self.wireframe_pipeline,
+                    ) = pipelines;
+                }
+            }
+            ShaderKind::Skybox => {
+                let pipeline = create_skybox_pipeline(
+                    &self.device,
+                    &self.skybox_pipeline_layout,
+                    &shader,
+                    format,
+                    cache,
+                );
+                if is_error_scope_clean(error_scope, kind) {
+                    self.skybox_pipeline = pipeline;
+                }
+            }
+            ShaderKind::Crosshair => {
+                let pipeline = create_crosshair_pipeline(
+                    &self.device,
+                    &self.crosshair_pipeline_layout,
+                    &shader,
+                    format,
+                    cache,
+                );
+                if is_error_scope_clean(error_scope, kind) {
+                    self.crosshair_pipeline = pipeline;
+                }
+            }
+            ShaderKind::Minimap => {
+                let pipeline = create_minimap_pipeline(
+                    &self.device,
+                    &self.minimap_pipeline_layout,
+                    &shader,
+                    format,
+                    cache,
+                );
+                if is_error_scope_clean(error_scope, kind) {
+                    self.minimap_pipeline = pipeline;
+                }
+            }
+            ShaderKind::FrameGraph => {
+                let pipeline = create_frame_graph_pipeline(
+                    &self.device,
+                    &self.frame_graph_pipeline_layout,
+                    &shader,
+                    format,
+                    cache,
+                );
+                if is_error_scope_clean(error_scope, kind) {
+                    self.frame_graph_pipeline = pipeline;
+                }
+            }
+            ShaderKind::DepthOfField => {
+                let pipeline = create_dof_pipeline(
+                    &self.device,
+                    &self.dof_pipeline_layout,
+                    &shader,
+                    format,
+                    cache,
+                );
+                if is_error_scope_clean(error_scope, kind) {
+                    self.dof_pipeline = pipeline;
+                }
+            }
+        }
+    }
+
     pub const fn toggle_show_fps(&mut self) {
         self.show_fps = !self.show_fps;
     }
+
+    pub const fn toggle_show_debug(&mut self) {
+        self.show_debug = !self.show_debug;
+    }
+
+    pub const fn toggle_minimap(&mut self) {
+        self.show_minimap = !self.show_minimap;
+    }
+
+    // north is up, centered on the current chunk, the player is the white dot in the middle
+    fn update_minimap(&mut self, world: &World) {
+        let camera_chunk = camera_to_chunk_coords(self.camera.position());
+        if !self.show_minimap || self.minimap_center == Some(camera_chunk) {
+            return;
+        }
+        let previous_center = self.minimap_center.replace(camera_chunk);
+
+        let ChunkCoords { cx, cy } = camera_chunk;
+        let chunk_width = CHUNK_WIDTH as i32;
+        let pixels_per_chunk = chunk_width / MINIMAP_BLOCKS_PER_PIXEL;
+        let size = MINIMAP_SIZE as i32;
+        let half = size / 2;
+        let mut colors = Vec::with_capacity((size * size) as usize);
+        for row in 0..size {
+            for col in 0..size {
+                // sampling the whole map takes tens of milliseconds, so the pixels that were
+                // already on the previous map are shifted and only the new edges are sampled
+                let previous = previous_center.and_then(|previous| {
+                    let previous_col = col + (cx - previous.cx) * pixels_per_chunk;
+                    let previous_row = row - (cy - previous.cy) * pixels_per_chunk;
+                    ((0..size).contains(&previous_col) && (0..size).contains(&previous_row))
+                        .then(|| self.minimap_colors[(previous_row * size + previous_col) as usize])
+                });
+                colors.push(previous.unwrap_or_else(|| {
+                    world
+                        .get_biome_at(
+                            cx * chunk_width
+                                + chunk_width / 2
+                                + (col - half) * MINIMAP_BLOCKS_PER_PIXEL,
+                            cy * chunk_width
+                                + chunk_width / 2
+                                + (half - row) * MINIMAP_BLOCKS_PER_PIXEL,
+                        )
+                        .map_color()
+                }));
+            }
+        }
+
+        let pixels: Vec<u8> = colors
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &[r, g, b])| {
+                let (row, col) = ((i as i32).div_euclid(size), (i as i32).rem_euclid(size));
+                let is_player = (col - half).abs() <= 1 && (row - half).abs() <= 1;
+                if is_player { [255; 4] } else { [r, g, b, 255] }
+            })
+            .collect();
+        self.minimap_colors = colors;
+
+        self.queue.write_texture(
+            self.minimap_texture.as_image_copy(),
+            &pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(MINIMAP_SIZE * 4),
+                rows_per_image: Some(MINIMAP_SIZE),
+            },
+            self.minimap_texture.size(),
+        );
+    }
+
+    pub const fn toggle_frame_graph(&mut self) {
+        self.show_frame_graph = !self.show_frame_graph;
+    }
+
+    // frame times in milliseconds, oldest first, only the last FRAME_GRAPH_LEN are drawn
+    pub fn draw_frame_time_graph(&mut self, times: &[f32]) {
+        if !self.show_frame_graph {
+            return;
+        }
+        let times = &times[times.len().saturating_sub(FRAME_GRAPH_LEN)..];
+        let mut frame_times = [0.0; FRAME_GRAPH_LEN];
+        frame_times[..times.len()].copy_from_slice(times);
+
+        self.frame_graph_count = times.len() as u32;
+        self.worst_frame_time = times.iter().copied().fold(0.0, f32::max);
+        self.queue.write_buffer(
+            &self.frame_graph_buffer,
+            0,
+            bytemuck::bytes_of(&FrameGraphUniform {
+                origin: [
+                    FRAME_GRAPH_MARGIN,
+                    self.size.height as f32 - FRAME_GRAPH_MARGIN,
+                ],
+                screen_size: [self.size.width as f32, self.size.height as f32],
+                frame_times: bytemuck::cast(frame_times),
+            }),
+        );
+    }
+
+    pub const fn toggle_depth_of_field(&mut self) {
+        self.show_depth_of_field = !self.show_depth_of_field;
+    }
+
+    pub fn toggle_show_wireframe(&mut self) {
+        if self.wireframe_pipeline.is_none() {
+            log::warn!("wireframe is not supported by this adapter");
+            return;
+        }
+        self.show_wireframe = !self.show_wireframe;
+    }
 }
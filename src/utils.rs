@@ -1,9 +1,17 @@
-use crate::coords::WorldCoords;
-
 pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + t * (b - a)
 }
 
+// eases in and out of the [0, 1] range of t
+pub fn smoothstep(a: f32, b: f32, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    lerp(a, b, t * t * (3.0 - 2.0 * t))
+}
+
+pub fn inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+    (value - a) / (b - a)
+}
+
 pub fn sign(x: f32) -> i32 {
     if x > 0.0 {
         1
@@ -14,7 +22,14 @@ pub fn sign(x: f32) -> i32 {
     }
 }
 
-pub fn prf_i32x3_mod((x, y, z): WorldCoords, m: u64) -> u64 {
+// deterministic "random" integer in [0, m) for a 3d integer key, used to scatter features
+// over the world without storing any state. xor a salt into one of the coordinates to get
+// independent rolls for the same position.
+// the key is hashed into a 64-bit seed and scrambled with the splitmix64 finalizer, then
+// mapped to [0, m) with Lemire's multiply-high method. the few low products that would make
+// some results more likely than others are rejected and the seed is stepped, so there is no
+// modulo bias.
+pub fn prf_i32x3_mod((x, y, z): (i32, i32, i32), m: u64) -> u64 {
     debug_assert!(m > 0);
 
     #[inline]
@@ -44,3 +59,9 @@ pub fn prf_i32x3_mod((x, y, z): WorldCoords, m: u64) -> u64 {
         seed = seed.wrapping_add(STEP);
     }
 }
+
+// true with probability numerator / denominator, e.g. (key, 1, 200) for a 1 in 200 chance
+pub fn prf_i32x3_bool(key: (i32, i32, i32), numerator: u64, denominator: u64) -> bool {
+    debug_assert!(numerator <= denominator);
+    prf_i32x3_mod(key, denominator) < numerator
+}
@@ -5,14 +5,18 @@ pub struct Vertex {
     pub normal: [f32; 3],
     pub tex_coords: [f32; 2],
     pub atlas_offset: [u32; 2],
+    pub light: f32,
+    pub ao: f32,
 }
 
 impl Vertex {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
         0 => Float32x3,
         1 => Float32x3,
         2 => Float32x2,
         3 => Uint32x2,
+        4 => Float32,
+        5 => Float32,
     ];
 
     pub const fn desc() -> wgpu::VertexBufferLayout<'static> {
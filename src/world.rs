@@ -2,147 +2,307 @@
 
 use {
     crate::{
-        biome::BiomeType,
+        aabb::AABB,
+        biome::{BiomeType, Precipitation},
         block::BlockType,
-        camera::Camera,
-        chunk::{AdjacentChunks, CHUNK_HEIGHT, CHUNK_WIDTH, Chunk},
+        camera::{Camera, PLAYER_EYE_HEIGHT, player_aabb},
+        chunk::{
+            AdjacentChunks, Blocks, CHUNK_HEIGHT, CHUNK_MEMORY_ESTIMATE, CHUNK_WIDTH, Chunk,
+            ChunkMesh,
+        },
+        config::WorldConfig,
         coords::{
-            BlockCoords, ChunkCoords, WorldCoords, camera_to_world_coords, chunk_distance,
-            chunk_distance_squared, split_coords,
+            BlockCoords, ChunkCoords, WorldCoords, affected_chunks, camera_to_world_coords,
+            chunk_distance, chunk_distance_squared, split_coords,
         },
-        noise::{SimplexNoise, SimplexNoiseInfo},
-        spline::{Spline, SplinePoint},
-        state::{MEMORY_DISTANCE, RENDER_DISTANCE},
-        utils::{lerp, prf_i32x3_mod, sign},
-        vertex::Vertex,
+        face::Face,
+        modification_store::ModificationStore,
+        noise::{Noise2d, SimplexNoise, VoronoiNoise},
+        raycast::{RaycastIter, entry_face},
+        spline::Spline,
+        state::MEMORY_DISTANCE,
+        utils::{inverse_lerp, prf_i32x3_bool, prf_i32x3_mod, smoothstep},
     },
+    bincode::config,
     glam::Vec3,
     std::{
-        collections::{HashMap, HashSet},
+        array,
+        collections::{HashMap, HashSet, VecDeque},
+        f32::consts::TAU,
+        fs::File,
+        io::{self, BufReader, BufWriter},
         num::NonZero,
+        path::Path,
+        sync::{
+            Arc, Mutex,
+            mpsc::{self, Receiver, Sender},
+        },
         thread,
     },
 };
 
 pub const SURFACE: usize = 65;
 pub const SEA: usize = 63;
-pub const MAGMA_CORE: usize = 31;
+pub const MAGMA_CORE: usize = 8;
+
+// filled with basalt up to here, even under oceans
+const SOLID_BASALT_TOP: usize = 39;
+// deeper stone is basalt
+const BASALT_TOP: usize = 47;
+
+pub const CAVE_THRESHOLD: f32 = 0.55;
+const CAVE_ORE_MARGIN: f32 = 0.05;
+const CLIFF_STEEPNESS: f32 = 40.0;
+const VORONOI_TEMPERATURE_FREQUENCY: f32 = 0.001;
+
+const CANOPY_RADIUS: i32 = 2;
+
+// surface blocks are dithered over this distance around biome borders
+const BIOME_BLEND_RADIUS: i32 = 4;
+const BIOME_BLEND_SALT: i32 = 0x5EED_B10D;
+// grass turns to snow around this height where it snows
+const SNOW_LINE: f32 = 100.0;
+const SNOW_LINE_BLEND: f32 = 8.0;
+const SNOW_LINE_SALT: i32 = 0x5_0E11;
+
+// a 1 in DUNGEON_CHANCE chunks holds a room, walls, floor and ceiling included
+const DUNGEON_CHANCE: u64 = 200;
+const DUNGEON_WIDTH: usize = 7;
+const DUNGEON_HEIGHT: usize = 5;
+
+// absolute z ranges, the part inside the magma core is skipped
+struct OreVein {
+    ore: BlockType,
+    min_z: usize,
+    max_z: usize,
+    chance: u64,
+    radius: i32,
+}
+
+const ORE_VEINS: [OreVein; 4] = [
+    OreVein {
+        ore: BlockType::CoalOre,
+        min_z: 32,
+        max_z: 80,
+        chance: 1500,
+        radius: 2,
+    },
+    OreVein {
+        ore: BlockType::IronOre,
+        min_z: 16,
+        max_z: 64,
+        chance: 2500,
+        radius: 2,
+    },
+    OreVein {
+        ore: BlockType::GoldOre,
+        min_z: 0,
+        max_z: 32,
+        chance: 4000,
+        radius: 1,
+    },
+    OreVein {
+        ore: BlockType::EmeraldOre,
+        min_z: 4,
+        max_z: 16,
+        chance: 6000,
+        radius: 1,
+    },
+];
+
+// in seconds, starting and wrapping at midnight
+pub const DAY_LENGTH: f32 = 600.0;
+const START_TIME: f32 = 0.3 * DAY_LENGTH; // shortly after sunrise
 
 pub const MAX_DELETE_DISTANCE: f32 = 48.0;
+const DELETION_HISTORY_LEN: usize = 32;
+pub const EXPLOSION_RADIUS: f32 = 4.0;
+const MAX_FILL_SIZE: i32 = 32;
 
+// further away the modifications are moved out of memory into the modification store
+const DELETED_BLOCKS_DISTANCE: i32 = 2 * MEMORY_DISTANCE;
+const COLUMN_CACHE_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy)]
 pub struct NoiseValues {
-    temperature: f32,
-    humidity: f32,
-    continentalness: f32,
-    erosion: f32,
-    weirdness: f32,
-    pv: f32,
+    pub temperature: f32,
+    pub humidity: f32,
+    pub continentalness: f32,
+    pub erosion: f32,
+    pub weirdness: f32,
+    pub pv: f32,
 }
 
-pub struct World {
-    temperature_noise: SimplexNoise,
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnInfo {
+    pub height: usize,
+    pub biome: BiomeType,
+    pub noise: NoiseValues,
+}
+
+// everything about a column that doesn't depend on z
+struct ColumnTerrain {
+    height: usize,
+    biome: BiomeType,
+    surface_block: BlockType,
+    subsurface_block: BlockType,
+    subsurface_depth: usize,
+    cliff: bool,
+    // caves are only carved between these heights (inclusive)
+    cave_low: usize,
+    cave_high: usize,
+}
+
+type ChunkColumns = [[ColumnTerrain; CHUNK_WIDTH]; CHUNK_WIDTH];
+
+#[derive(Debug, Clone, Copy)]
+pub enum WorldGeneratorKind {
+    Default,
+    // `block` below `height`, a layer of grass at `height` and nothing above
+    Flat { height: usize, block: BlockType },
+}
+
+pub struct ChunkGenTask {
+    chunk_coords: ChunkCoords,
+    blocks: Box<Blocks>,
+}
+
+// the noise-based terrain generation, shared with the chunk generation workers
+pub struct WorldGenerator {
+    kind: WorldGeneratorKind,
+
+    temperature_noise: Noise2d,
     humidity_noise: SimplexNoise,
     continentalness_noise: SimplexNoise,
     erosion_noise: SimplexNoise,
     weirdness_noise: SimplexNoise,
 
-    cave_low_noise: SimplexNoise,
-    cave_high_noise: SimplexNoise,
+    cave_noise_3d: SimplexNoise,
 
-    pub chunks: HashMap<ChunkCoords, Chunk>,
-    deleted_blocks: HashMap<ChunkCoords, HashSet<BlockCoords>>,
+    continentalness_spline: Spline,
+    erosion_spline: Spline,
+    peaks_valleys_spline: Spline,
+    ore_salt: i32,
+    dungeon_salt: i32,
 }
-impl World {
-    pub fn new(seed: u64) -> Self {
-        // temperature: affects hot vs cold biomes
-        let temperature_noise = SimplexNoise::new(
-            seed.wrapping_add(0xFF446677),
-            SimplexNoiseInfo {
-                frequency: 0.000336,
-                octaves: 2,
-                ..Default::default()
-            },
-        );
 
-        // humidity: affects dry vs wet biomes
-        let humidity_noise = SimplexNoise::new(
-            seed.wrapping_add(0xAABB33CC),
-            SimplexNoiseInfo {
-                frequency: 0.000246,
-                octaves: 2,
-                persistence: 0.6,
-                ..Default::default()
-            },
-        );
-
-        // continentalness: determines land vs ocean
-        let continentalness_noise = SimplexNoise::new(
-            seed.wrapping_add(0xFF000055),
-            SimplexNoiseInfo {
-                frequency: 0.000974,
-                octaves: 6,
-                persistence: 0.8,
-                lacunarity: 1.2,
-            },
-        );
+pub struct World {
+    generator: Arc<WorldGenerator>,
 
-        // erosion: affects terrain ruggedness
-        let erosion_noise = SimplexNoise::new(
-            seed.wrapping_add(0x44336699),
-            SimplexNoiseInfo {
-                frequency: 0.00998,
-                octaves: 6,
-                persistence: 0.42,
-                ..Default::default()
-            },
-        );
+    pub chunks: HashMap<ChunkCoords, Chunk>,
+    deleted_blocks: HashMap<ChunkCoords, HashSet<BlockCoords>>,
+    placed_blocks: HashMap<ChunkCoords, HashMap<BlockCoords, BlockType>>,
+    evicted_modifications: ModificationStore,
+    // single block deletions, most recent last
+    deletion_history: VecDeque<(WorldCoords, BlockType)>,
 
-        // weirdness: creates unusual terrain features
-        let weirdness_noise = SimplexNoise::new(
-            seed.wrapping_add(0xFF110077),
-            SimplexNoiseInfo {
-                frequency: 0.00196,
-                octaves: 6,
-                persistence: 0.66,
-                ..Default::default()
-            },
-        );
+    pending_chunks: HashSet<ChunkCoords>,
+    job_sender: Sender<ChunkCoords>,
+    task_receiver: Receiver<ChunkGenTask>,
 
-        // cave lower bound
-        let cave_low_noise = SimplexNoise::new(
-            seed.wrapping_add(0x1F326321),
-            SimplexNoiseInfo {
-                frequency: 0.007,
-                octaves: 6,
-                persistence: 0.6,
-                lacunarity: 2.0,
-            },
-        );
+    // each entry remembers the tick of its last access
+    column_cache: HashMap<(i32, i32), (ColumnInfo, u64)>,
+    column_cache_tick: u64,
 
-        // cave upper bound
-        let cave_high_noise = SimplexNoise::new(
-            seed.wrapping_add(0x15444555),
-            SimplexNoiseInfo {
-                frequency: 0.007,
-                octaves: 6,
-                persistence: 0.6,
-                lacunarity: 2.0,
-            },
-        );
+    pub time: f32,
+}
+impl World {
+    pub fn new(
+        seed: u64,
+        voronoi_temperature: bool,
+        config: &WorldConfig,
+        kind: WorldGeneratorKind,
+    ) -> Self {
+        let generator = Arc::new(WorldGenerator::new(seed, voronoi_temperature, config, kind));
+
+        let (job_sender, job_receiver) = mpsc::channel::<ChunkCoords>();
+        let (task_sender, task_receiver) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers = thread::available_parallelism().map_or(1, NonZero::get);
+        for _ in 0..workers {
+            let generator = Arc::clone(&generator);
+            let job_receiver = Arc::clone(&job_receiver);
+            let task_sender = task_sender.clone();
+            // workers exit once the world (and thus the job sender) is dropped
+            thread::spawn(move || {
+                loop {
+                    let job = job_receiver.lock().unwrap().recv();
+                    let Ok(chunk_coords) = job else {
+                        break;
+                    };
+                    let blocks = Box::new(generator.generate_chunk_blocks(chunk_coords));
+                    let task = ChunkGenTask {
+                        chunk_coords,
+                        blocks,
+                    };
+                    if task_sender.send(task).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
 
         Self {
-            temperature_noise,
-            humidity_noise,
-            continentalness_noise,
-            erosion_noise,
-            weirdness_noise,
-            cave_low_noise,
-            cave_high_noise,
+            generator,
             chunks: HashMap::new(),
             deleted_blocks: HashMap::new(),
+            placed_blocks: HashMap::new(),
+            evicted_modifications: ModificationStore::new(),
+            deletion_history: VecDeque::with_capacity(DELETION_HISTORY_LEN),
+            pending_chunks: HashSet::new(),
+            job_sender,
+            task_receiver,
+            column_cache: HashMap::new(),
+            column_cache_tick: 0,
+            time: START_TIME,
         }
     }
 
+    // the save file only holds the player's modifications, terrain comes from the seed
+    pub fn load(
+        path: &Path,
+        seed: u64,
+        voronoi_temperature: bool,
+        config: &WorldConfig,
+        kind: WorldGeneratorKind,
+    ) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (deleted_blocks, placed_blocks) =
+            bincode::decode_from_std_read(&mut reader, config::standard())
+                .map_err(io::Error::other)?;
+        Ok(Self {
+            deleted_blocks,
+            placed_blocks,
+            ..Self::new(seed, voronoi_temperature, config, kind)
+        })
+    }
+
+    // the evicted modifications are merged back, the save file holds all of them
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut deleted_blocks = self.deleted_blocks.clone();
+        let mut placed_blocks = self.placed_blocks.clone();
+        for (chunk_coords, (deleted, placed)) in self.evicted_modifications.read_all()? {
+            deleted_blocks
+                .entry(chunk_coords)
+                .or_default()
+                .extend(deleted);
+            placed_blocks
+                .entry(chunk_coords)
+                .or_default()
+                .extend(placed);
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::encode_into_std_write(
+            (&deleted_blocks, &placed_blocks),
+            &mut writer,
+            config::standard(),
+        )
+        .map_err(io::Error::other)?;
+        Ok(())
+    }
+
     pub fn get_chunk_if_loaded(&self, chunk_coords: ChunkCoords) -> Option<&Chunk> {
         self.chunks.get(&chunk_coords)
     }
@@ -153,30 +313,593 @@ impl World {
 
     pub fn load_chunk(&mut self, chunk_coords: ChunkCoords) -> &Chunk {
         if !self.chunks.contains_key(&chunk_coords) {
-            let mut blocks = self.generate_chunk_blocks(chunk_coords);
-            if let Some(deleted) = self.deleted_blocks.get(&chunk_coords) {
-                for &(x, y, z) in deleted {
-                    blocks[x][y][z] = None;
-                }
-            }
-            let chunk = Chunk::new(chunk_coords, blocks);
-            self.chunks.insert(chunk_coords, chunk);
+            let blocks = self.generator.generate_chunk_blocks(chunk_coords);
+            self.insert_chunk(chunk_coords, blocks);
         }
 
         &self.chunks[&chunk_coords]
     }
 
+    // generates synchronously, nearest first, returns how many chunks were missing
+    pub fn preload_circle(&mut self, center: ChunkCoords, radius: i32) -> usize {
+        let mut chunks = (-radius..=radius)
+            .flat_map(|dx| (-radius..=radius).map(move |dy| center.offset(dx, dy)))
+            .filter(|&chunk_coords| chunk_distance_squared(center, chunk_coords) <= radius * radius)
+            .filter(|chunk_coords| !self.chunks.contains_key(chunk_coords))
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|&chunk_coords| chunk_distance_squared(center, chunk_coords));
+
+        for &chunk_coords in &chunks {
+            self.load_chunk(chunk_coords);
+        }
+        chunks.len()
+    }
+
+    fn insert_chunk(&mut self, chunk_coords: ChunkCoords, mut blocks: Blocks) {
+        self.restore_modifications(chunk_coords);
+        if let Some(deleted) = self.deleted_blocks.get(&chunk_coords) {
+            for &BlockCoords { bx, by, bz } in deleted {
+                blocks[bx][by][bz] = None;
+            }
+        }
+        if let Some(placed) = self.placed_blocks.get(&chunk_coords) {
+            for (&BlockCoords { bx, by, bz }, &block) in placed {
+                blocks[bx][by][bz] = Some(block);
+            }
+        }
+        let chunk = Chunk::new(chunk_coords, blocks);
+        self.chunks.insert(chunk_coords, chunk);
+    }
+
+    // generates the chunk in the background, see `receive_generated_chunks`
+    pub fn request_chunk(&mut self, chunk_coords: ChunkCoords) {
+        if self.chunks.contains_key(&chunk_coords) || !self.pending_chunks.insert(chunk_coords) {
+            return;
+        }
+        self.job_sender.send(chunk_coords).unwrap();
+    }
+
+    pub fn request_neighborhood(&mut self, chunk_coords: ChunkCoords) {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                self.request_chunk(chunk_coords.offset(dx, dy));
+            }
+        }
+    }
+
+    // loads the chunks generated since the last call and returns their coordinates
+    pub fn receive_generated_chunks(&mut self) -> Vec<ChunkCoords> {
+        let mut received = Vec::new();
+        while let Ok(ChunkGenTask {
+            chunk_coords,
+            blocks,
+        }) = self.task_receiver.try_recv()
+        {
+            self.pending_chunks.remove(&chunk_coords);
+            // the chunk may have been loaded synchronously in the meantime
+            if !self.chunks.contains_key(&chunk_coords) {
+                self.insert_chunk(chunk_coords, *blocks);
+                received.push(chunk_coords);
+            }
+        }
+        received
+    }
+
+    // a chunk can only be meshed once its neighbors are loaded too
+    pub fn is_neighborhood_loaded(&self, chunk_coords: ChunkCoords) -> bool {
+        (-1..=1).all(|dx| (-1..=1).all(|dy| self.chunks.contains_key(&chunk_coords.offset(dx, dy))))
+    }
+
+    // eye position one block above the highest block of the column
+    pub fn find_spawn_point(&mut self, world_x: i32, world_y: i32) -> Vec3 {
+        let (chunk_coords, BlockCoords { bx, by, .. }) =
+            split_coords(WorldCoords::new(world_x, world_y, 0)).unwrap();
+        let chunk = self.load_chunk(chunk_coords);
+        let surface = (0..CHUNK_HEIGHT)
+            .rev()
+            .find(|&bz| chunk.get_block(BlockCoords::new(bx, by, bz)).is_some())
+            .map_or(0, |z| z + 1);
+
+        Vec3::new(
+            world_x as f32 + 0.5,
+            world_y as f32 + 0.5,
+            surface as f32 + PLAYER_EYE_HEIGHT,
+        )
+    }
+
+    pub fn get_noise_values_at(&self, world_x: i32, world_y: i32) -> NoiseValues {
+        self.generator.get_noise_values(world_x, world_y)
+    }
+
+    pub fn determine_biome(&self, values: &NoiseValues) -> BiomeType {
+        self.generator.determine_biome(values)
+    }
+
+    // least recently used columns are evicted in bulk once the cache is full
+    pub fn get_column_info(&mut self, world_x: i32, world_y: i32) -> ColumnInfo {
+        self.column_cache_tick += 1;
+        let tick = self.column_cache_tick;
+        if let Some((info, last_used)) = self.column_cache.get_mut(&(world_x, world_y)) {
+            *last_used = tick;
+            return *info;
+        }
+
+        if self.column_cache.len() >= COLUMN_CACHE_SIZE {
+            let oldest_kept = tick.saturating_sub(COLUMN_CACHE_SIZE as u64 / 2);
+            self.column_cache
+                .retain(|_, &mut (_, last_used)| last_used >= oldest_kept);
+        }
+        let info = self.generator.get_column_info(world_x, world_y);
+        self.column_cache.insert((world_x, world_y), (info, tick));
+        info
+    }
+
+    // the column the camera is in, floored so that negative coordinates don't round toward zero
+    pub fn get_column_info_under(&mut self, camera_pos: Vec3) -> ColumnInfo {
+        let WorldCoords { x, y, .. } = camera_to_world_coords(camera_pos);
+        self.get_column_info(x, y)
+    }
+
+    pub fn get_biome_at(&self, world_x: i32, world_y: i32) -> BiomeType {
+        self.determine_biome(&self.get_noise_values_at(world_x, world_y))
+    }
+
+    pub fn get_sky_color_at(&mut self, camera_pos: Vec3) -> [f32; 3] {
+        self.get_column_info_under(camera_pos).biome.sky_color()
+    }
+
+    pub fn get_fog_color_at(&mut self, camera_pos: Vec3) -> [f32; 3] {
+        self.get_column_info_under(camera_pos).biome.fog_color()
+    }
+
+    pub fn get_water_color_at(&mut self, camera_pos: Vec3) -> [f32; 4] {
+        self.get_column_info_under(camera_pos).biome.water_color()
+    }
+
+    pub fn advance_time(&mut self, dt: f32) {
+        self.time = (self.time + dt).rem_euclid(DAY_LENGTH);
+    }
+
+    // the sun rises in the east and sets in the west, right above at noon
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = self.time / DAY_LENGTH * TAU;
+        Vec3::new(angle.sin(), 0.0, -angle.cos())
+    }
+
+    pub fn chunks_loaded(&self) -> usize {
+        self.chunks.len()
+    }
+
+    // the evicted ones are not counted
+    pub fn deleted_block_count(&self) -> usize {
+        self.deleted_blocks.values().map(HashSet::len).sum()
+    }
+
+    // rough, the meshes live on the gpu side and are counted by the state
+    pub fn memory_estimate_bytes(&self) -> usize {
+        let placed_block_count: usize = self.placed_blocks.values().map(HashMap::len).sum();
+        self.chunks_loaded() * CHUNK_MEMORY_ESTIMATE
+            + self.deleted_block_count() * size_of::<BlockCoords>()
+            + placed_block_count * size_of::<(BlockCoords, BlockType)>()
+    }
+
     pub fn discard_far_chunks(&mut self, current_chunk: ChunkCoords) {
         self.chunks.retain(|&other_chunk, _| {
             chunk_distance_squared(current_chunk, other_chunk) <= MEMORY_DISTANCE * MEMORY_DISTANCE
         });
+        // kept in memory a bit longer than the chunks themselves so that they survive a chunk
+        // briefly leaving and re-entering memory range, then written to the modification store
+        let far_chunks = self
+            .deleted_blocks
+            .keys()
+            .chain(self.placed_blocks.keys())
+            .copied()
+            .filter(|&other_chunk| {
+                chunk_distance_squared(current_chunk, other_chunk)
+                    > DELETED_BLOCKS_DISTANCE * DELETED_BLOCKS_DISTANCE
+            })
+            .collect::<HashSet<_>>();
+        for chunk_coords in far_chunks {
+            let modifications = (
+                self.deleted_blocks
+                    .remove(&chunk_coords)
+                    .unwrap_or_default(),
+                self.placed_blocks.remove(&chunk_coords).unwrap_or_default(),
+            );
+            if let Err(err) = self
+                .evicted_modifications
+                .store(chunk_coords, &modifications)
+            {
+                // rather kept in memory than lost
+                log::warn!("failed to evict the modifications of {chunk_coords:?}: {err}");
+                let (deleted, placed) = modifications;
+                self.deleted_blocks.insert(chunk_coords, deleted);
+                self.placed_blocks.insert(chunk_coords, placed);
+            }
+        }
+    }
+
+    fn restore_modifications(&mut self, chunk_coords: ChunkCoords) {
+        match self.evicted_modifications.take(chunk_coords) {
+            Ok(Some((deleted, placed))) => {
+                self.deleted_blocks
+                    .entry(chunk_coords)
+                    .or_default()
+                    .extend(deleted);
+                self.placed_blocks
+                    .entry(chunk_coords)
+                    .or_default()
+                    .extend(placed);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                log::warn!("failed to restore the modifications of {chunk_coords:?}: {err}");
+            }
+        }
+    }
+
+    pub fn generate_chunk_mesh(
+        &mut self,
+        chunk_coords: ChunkCoords,
+        camera_coords: ChunkCoords,
+        render_distance: f32,
+        greedy: bool,
+    ) -> ChunkMesh {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                self.load_chunk(chunk_coords.offset(dx, dy));
+            }
+        }
+
+        let chunk = self.get_chunk_if_loaded(chunk_coords).unwrap();
+
+        let neighbor = |dx: i32, dy: i32| {
+            let coords = chunk_coords.offset(dx, dy);
+            (chunk_distance(camera_coords, coords) < render_distance)
+                .then(|| self.get_chunk_if_loaded(coords).unwrap())
+        };
+
+        let adjacent = AdjacentChunks {
+            north: neighbor(0, 1),
+            south: neighbor(0, -1),
+            east: neighbor(1, 0),
+            west: neighbor(-1, 0),
+            northeast: neighbor(1, 1),
+            northwest: neighbor(-1, 1),
+            southeast: neighbor(1, -1),
+            southwest: neighbor(-1, -1),
+        };
+
+        if greedy {
+            chunk.generate_greedy_mesh(&adjacent)
+        } else {
+            chunk.generate_mesh(&adjacent)
+        }
+    }
+
+    pub fn delete_center_block(&mut self, camera: &Camera) -> Option<(WorldCoords, BlockType)> {
+        let (_, world_coords, block, _) =
+            self.find_block_in_dir(camera.position(), camera.direction(), MAX_DELETE_DISTANCE)?;
+        self.delete_block(world_coords);
+        Some((world_coords, block))
+    }
+
+    pub fn explode_center_block(&mut self, camera: &Camera) -> HashSet<ChunkCoords> {
+        self.find_block_in_dir(camera.position(), camera.direction(), MAX_DELETE_DISTANCE)
+            .map(|(_, center, ..)| self.explode(center, EXPLOSION_RADIUS))
+            .unwrap_or_default()
+    }
+
+    // returns the chunks that need to be remeshed
+    pub fn explode(&mut self, center: WorldCoords, radius: f32) -> HashSet<ChunkCoords> {
+        let r = radius.ceil() as i32;
+        let mut deletions: HashMap<ChunkCoords, Vec<(BlockCoords, Option<BlockType>)>> =
+            HashMap::new();
+        let mut dirty_chunks = HashSet::new();
+        let WorldCoords {
+            x: cx,
+            y: cy,
+            z: cz,
+        } = center;
+
+        for x in cx - r..=cx + r {
+            for y in cy - r..=cy + r {
+                for z in cz - r..=cz + r {
+                    let distance_squared = (x - cx).pow(2) + (y - cy).pow(2) + (z - cz).pow(2);
+                    if distance_squared as f32 > radius * radius {
+                        continue;
+                    }
+
+                    let world_coords = WorldCoords::new(x, y, z);
+                    // fluids and ice absorb the blast
+                    if !self
+                        .get_block(world_coords)
+                        .is_some_and(|block| !matches!(block, BlockType::Water | BlockType::Ice))
+                    {
+                        continue;
+                    }
+
+                    let (chunk_coords, block_coords) = split_coords(world_coords).unwrap();
+                    deletions
+                        .entry(chunk_coords)
+                        .or_default()
+                        .push((block_coords, None));
+                    dirty_chunks.extend(affected_chunks(world_coords));
+                }
+            }
+        }
+
+        self.apply_changes(deletions);
+        dirty_chunks
+    }
+
+    // fills the box between two corners (inclusive) except where the player stands,
+    // returns the chunks that need to be remeshed
+    pub fn fill(
+        &mut self,
+        camera: &Camera,
+        from: WorldCoords,
+        to: WorldCoords,
+        block: BlockType,
+    ) -> HashSet<ChunkCoords> {
+        let player = player_aabb(camera.position());
+        self.fill_region(from, to, Some(block), Some(&player))
+    }
+
+    // deletes the box between two corners (inclusive), returns the chunks that need to be remeshed
+    pub fn delete_region(&mut self, from: WorldCoords, to: WorldCoords) -> HashSet<ChunkCoords> {
+        self.fill_region(from, to, None, None)
+    }
+
+    // boxes larger than MAX_FILL_SIZE along any axis are refused, the work is done in one frame
+    fn fill_region(
+        &mut self,
+        from: WorldCoords,
+        to: WorldCoords,
+        block: Option<BlockType>,
+        keep_clear: Option<&AABB>,
+    ) -> HashSet<ChunkCoords> {
+        let (min_x, max_x) = (from.x.min(to.x), from.x.max(to.x));
+        let (min_y, max_y) = (from.y.min(to.y), from.y.max(to.y));
+        let min_z = from.z.min(to.z).max(0);
+        let max_z = from.z.max(to.z).min(CHUNK_HEIGHT as i32 - 1);
+        if [max_x - min_x, max_y - min_y, max_z - min_z]
+            .into_iter()
+            .any(|extent| extent >= MAX_FILL_SIZE)
+        {
+            log::warn!("fill box from {from:?} to {to:?} is larger than {MAX_FILL_SIZE} blocks");
+            return HashSet::new();
+        }
+
+        let chunk_width = CHUNK_WIDTH as i32;
+        for chunk_x in min_x.div_euclid(chunk_width)..=max_x.div_euclid(chunk_width) {
+            for chunk_y in min_y.div_euclid(chunk_width)..=max_y.div_euclid(chunk_width) {
+                self.load_chunk(ChunkCoords::new(chunk_x, chunk_y));
+            }
+        }
+
+        let mut changes: HashMap<ChunkCoords, Vec<(BlockCoords, Option<BlockType>)>> =
+            HashMap::new();
+        let mut dirty_chunks = HashSet::new();
+
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                for z in min_z..=max_z {
+                    let world_coords = WorldCoords::new(x, y, z);
+                    if self.get_block(world_coords) == block {
+                        continue;
+                    }
+                    let block_min = Vec3::new(x as f32, y as f32, z as f32);
+                    if keep_clear.is_some_and(|aabb| {
+                        aabb.intersects_aabb(&AABB::new(block_min, block_min + Vec3::ONE))
+                    }) {
+                        continue;
+                    }
+
+                    let (chunk_coords, block_coords) = split_coords(world_coords).unwrap();
+                    changes
+                        .entry(chunk_coords)
+                        .or_default()
+                        .push((block_coords, block));
+                    dirty_chunks.extend(affected_chunks(world_coords));
+                }
+            }
+        }
+
+        self.apply_changes(changes);
+        dirty_chunks
+    }
+
+    // places against the face of the targeted block
+    pub fn place_block_in_dir(&mut self, camera: &Camera, block: BlockType) -> Option<WorldCoords> {
+        let (_, WorldCoords { x, y, z }, _, face) =
+            self.find_block_in_dir(camera.position(), camera.direction(), MAX_DELETE_DISTANCE)?;
+        let [nx, ny, nz] = face.normal();
+        let target = WorldCoords::new(x + nx as i32, y + ny as i32, z + nz as i32);
+        let block_min = Vec3::new(target.x as f32, target.y as f32, target.z as f32);
+        if player_aabb(camera.position())
+            .intersects_aabb(&AABB::new(block_min, block_min + Vec3::ONE))
+        {
+            return None;
+        }
+        self.place_block(target, block).then_some(target)
+    }
+
+    #[expect(dead_code)] // TODO: block selection cursor
+    pub fn pick_block_at_screen(
+        &self,
+        camera: &Camera,
+        ndc_x: f32,
+        ndc_y: f32,
+        max_distance: f32,
+    ) -> Option<(f32, WorldCoords, BlockType, Face)> {
+        let (origin, direction) = camera.get_pick_ray(ndc_x, ndc_y);
+        self.find_block_in_dir(origin, direction, max_distance)
+    }
+
+    pub fn find_block_in_dir(
+        &self,
+        pos: Vec3,
+        dir: Vec3,
+        max_distance: f32,
+    ) -> Option<(f32, WorldCoords, BlockType, Face)> {
+        self.raycast_all(pos, dir, max_distance)
+            .next()
+            .map(|(t, world_coords, block)| {
+                (t, world_coords, block, entry_face(pos, dir, world_coords))
+            })
+    }
+
+    // every block along the ray, nearest first
+    pub fn raycast_all(
+        &self,
+        pos: Vec3,
+        dir: Vec3,
+        max_distance: f32,
+    ) -> impl Iterator<Item = (f32, WorldCoords, BlockType)> {
+        RaycastIter::new(self, pos, dir, max_distance)
+    }
+
+    pub fn get_block(&self, world_coords: WorldCoords) -> Option<BlockType> {
+        let (chunk_coords, block_coords) = split_coords(world_coords)?;
+        let chunk = self.get_chunk_if_loaded(chunk_coords)?;
+        chunk.get_block(block_coords)
+    }
+
+    fn delete_block(&mut self, world_coords: WorldCoords) {
+        let Some((chunk_coords, block_coords)) = split_coords(world_coords) else {
+            return;
+        };
+
+        let Some(chunk) = self.get_mut_chunk_if_loaded(chunk_coords) else {
+            return;
+        };
+        let Some(block) = chunk.get_block(block_coords) else {
+            return;
+        };
+
+        chunk.delete_block(block_coords);
+        self.record_change(chunk_coords, block_coords, None);
+        if self.deletion_history.len() == DELETION_HISTORY_LEN {
+            self.deletion_history.pop_front();
+        }
+        self.deletion_history.push_back((world_coords, block));
+    }
+
+    // deletions whose spot has been filled or unloaded since are skipped
+    pub fn undo_last_deletion(&mut self) -> Option<(WorldCoords, BlockType)> {
+        while let Some((world_coords, block)) = self.deletion_history.pop_back() {
+            if self.place_block(world_coords, block) {
+                return Some((world_coords, block));
+            }
+        }
+        None
+    }
+
+    fn place_block(&mut self, world_coords: WorldCoords, block: BlockType) -> bool {
+        let Some((chunk_coords, block_coords)) = split_coords(world_coords) else {
+            return false;
+        };
+
+        let Some(chunk) = self.get_mut_chunk_if_loaded(chunk_coords) else {
+            return false;
+        };
+        if chunk.get_block(block_coords).is_some() {
+            return false;
+        }
+
+        chunk.place_block(block_coords, block);
+        self.record_change(chunk_coords, block_coords, Some(block));
+        true
+    }
+
+    // one tree rebuild per chunk instead of one per block
+    fn apply_changes(
+        &mut self,
+        changes: HashMap<ChunkCoords, Vec<(BlockCoords, Option<BlockType>)>>,
+    ) {
+        for (chunk_coords, changes) in changes {
+            self.chunks
+                .get_mut(&chunk_coords)
+                .unwrap()
+                .set_blocks(&changes);
+            for (block_coords, block) in changes {
+                self.record_change(chunk_coords, block_coords, block);
+            }
+        }
+    }
+
+    // keeps the modifications that are reapplied when the chunk is regenerated
+    fn record_change(
+        &mut self,
+        chunk_coords: ChunkCoords,
+        block_coords: BlockCoords,
+        block: Option<BlockType>,
+    ) {
+        if let Some(block) = block {
+            if let Some(deleted) = self.deleted_blocks.get_mut(&chunk_coords) {
+                deleted.remove(&block_coords);
+            }
+            self.placed_blocks
+                .entry(chunk_coords)
+                .or_default()
+                .insert(block_coords, block);
+        } else {
+            if let Some(placed) = self.placed_blocks.get_mut(&chunk_coords) {
+                placed.remove(&block_coords);
+            }
+            self.deleted_blocks
+                .entry(chunk_coords)
+                .or_default()
+                .insert(block_coords);
+        }
+    }
+}
+
+impl WorldGenerator {
+    pub fn new(
+        seed: u64,
+        voronoi_temperature: bool,
+        config: &WorldConfig,
+        kind: WorldGeneratorKind,
+    ) -> Self {
+        let temperature_noise = if voronoi_temperature {
+            // cold cell centers warming up towards the cell edges
+            Noise2d::Voronoi(VoronoiNoise::new(
+                seed.wrapping_add(0xFF446677),
+                VORONOI_TEMPERATURE_FREQUENCY,
+            ))
+        } else {
+            Noise2d::Simplex(Box::new(SimplexNoise::new(
+                seed.wrapping_add(0xFF446677),
+                config.temperature,
+            )))
+        };
+
+        Self {
+            kind,
+            temperature_noise,
+            humidity_noise: SimplexNoise::new(seed.wrapping_add(0xAABB33CC), config.humidity),
+            continentalness_noise: SimplexNoise::new(
+                seed.wrapping_add(0xFF000055),
+                config.continentalness,
+            ),
+            erosion_noise: SimplexNoise::new(seed.wrapping_add(0x44336699), config.erosion),
+            weirdness_noise: SimplexNoise::new(seed.wrapping_add(0xFF110077), config.weirdness),
+            cave_noise_3d: SimplexNoise::new(seed.wrapping_add(0x1F326321), config.cave),
+            continentalness_spline: Spline::catmull_rom(config.continentalness_spline.clone()),
+            erosion_spline: Spline::new(config.erosion_spline.clone()),
+            peaks_valleys_spline: Spline::catmull_rom(config.peaks_valleys_spline.clone()),
+            ore_salt: seed.wrapping_add(0x0E5E_1A7E) as i32,
+            dungeon_salt: seed.wrapping_add(0xD0_06E0) as i32,
+        }
     }
 
     fn generate_height_at(&self, values: &NoiseValues) -> f32 {
-        let continentalness_offset = self.continentalness_spline(values.continentalness);
-        let pv_offset = self.peaks_valleys_spline(values.pv);
+        let continentalness_offset = self.continentalness_spline.sample(values.continentalness);
+        let pv_offset = self.peaks_valleys_spline.sample(values.pv);
         let erosion_factor = if values.continentalness < -0.2 {
-            self.erosion_factor(values.erosion)
+            self.erosion_spline.sample(values.erosion)
         } else {
             1.0
         };
@@ -184,53 +907,11 @@ impl World {
         SURFACE as f32 + continentalness_offset + pv_offset * erosion_factor
     }
 
-    // Continentalness spline: higher continentalness = higher terrain
-    fn continentalness_spline(&self, continentalness: f32) -> f32 {
-        let spline = Spline::new(vec![
-            SplinePoint::new(-1.0, -40.0),
-            SplinePoint::new(-0.45, -20.0),
-            SplinePoint::new(-0.2, -2.0),
-            SplinePoint::new(-0.1, -1.0),
-            SplinePoint::new(0.15, 2.0),
-            SplinePoint::new(0.3, 8.0),
-            SplinePoint::new(0.5, 10.0),
-            SplinePoint::new(0.7, 18.0),
-            SplinePoint::new(0.8, 20.0),
-            SplinePoint::new(1.0, 30.0),
-        ]);
-
-        spline.sample(continentalness)
-    }
-
-    // Erosion spline: higher erosion = lower, flatter terrain
-    fn erosion_factor(&self, erosion: f32) -> f32 {
-        let spline = Spline::new(vec![
-            SplinePoint::new(-1.0, 1.0),
-            SplinePoint::new(-0.8, 0.9),
-            SplinePoint::new(-0.38, 0.8),
-            SplinePoint::new(-0.22, 0.6),
-            SplinePoint::new(0.05, 0.5),
-            SplinePoint::new(0.45, 0.4),
-            SplinePoint::new(0.9, 0.2),
-            SplinePoint::new(1.0, 0.1),
-        ]);
-
-        spline.sample(erosion)
-    }
-
-    // Peaks and valleys spline
-    fn peaks_valleys_spline(&self, peak_and_valley: f32) -> f32 {
-        let spline = Spline::new(vec![
-            SplinePoint::new(-1.0, -30.0),
-            SplinePoint::new(-0.9, 0.0),
-            SplinePoint::new(-0.2, 2.0),
-            SplinePoint::new(0.2, 10.0),
-            SplinePoint::new(0.6, 30.0),
-            SplinePoint::new(0.9, 60.0),
-            SplinePoint::new(1.0, 60.0),
-        ]);
-
-        spline.sample(peak_and_valley)
+    // terrain changes height fast across erosion bands: expose bare rock
+    fn is_cliff(&self, values: &NoiseValues) -> bool {
+        let slope = self.peaks_valleys_spline.sample(values.pv)
+            * self.erosion_spline.derivative(values.erosion);
+        values.continentalness < -0.2 && slope.abs() > CLIFF_STEEPNESS
     }
 
     #[expect(clippy::cognitive_complexity)]
@@ -529,8 +1210,21 @@ impl World {
         }
     }
 
-    fn get_ore(world_coords: WorldCoords, base_stone: BlockType) -> BlockType {
-        match prf_i32x3_mod(world_coords, 200) {
+    pub fn get_column_info(&self, world_x: i32, world_y: i32) -> ColumnInfo {
+        let noise = self.get_noise_values(world_x, world_y);
+        let height = match self.kind {
+            WorldGeneratorKind::Default => self.generate_height_at(&noise) as usize,
+            WorldGeneratorKind::Flat { height, .. } => height,
+        };
+        ColumnInfo {
+            height,
+            biome: self.determine_biome(&noise),
+            noise,
+        }
+    }
+
+    fn get_ore(WorldCoords { x, y, z }: WorldCoords, base_stone: BlockType) -> BlockType {
+        match prf_i32x3_mod((x, y, z), 200) {
             0 => BlockType::RedStone,
             1 => BlockType::GoldOre,
             2 => BlockType::EmeraldOre,
@@ -538,228 +1232,346 @@ impl World {
         }
     }
 
-    fn generate_chunk_blocks(
-        &self,
-        (chunk_x, chunk_y): ChunkCoords,
-    ) -> [[[Option<BlockType>; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH] {
-        let mut blocks = [[[None; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH];
-
-        let workers = thread::available_parallelism()
-            .map(NonZero::get)
-            .unwrap_or(1)
-            .min(CHUNK_WIDTH);
-        let chunk_size = CHUNK_WIDTH.div_ceil(workers);
-
-        thread::scope(|s| {
-            let mut remainder: &mut [[[Option<BlockType>; CHUNK_HEIGHT]; CHUNK_WIDTH]] =
-                &mut blocks;
-            let mut start_x = 0;
-
-            for _ in 0..workers {
-                let len = (CHUNK_WIDTH - start_x).min(chunk_size);
-                let (head, tail) = remainder.split_at_mut(len);
-                let start_x_this = start_x;
-
-                s.spawn(move || {
-                    for (dx, plane) in head.iter_mut().enumerate() {
-                        let x = start_x_this + dx;
-                        let world_x = (chunk_x * CHUNK_WIDTH as i32) + x as i32;
-
-                        for (y, column) in plane.iter_mut().enumerate() {
-                            let world_y = (chunk_y * CHUNK_WIDTH as i32) + y as i32;
-                            let noise_values = self.get_noise_values(world_x, world_y);
-                            let height = self.generate_height_at(&noise_values) as usize;
-                            let biome = self.determine_biome(&noise_values);
-
-                            // DON'T CHANGE UNTIL FT_VOX PUSH
-                            let cave_low =
-                                self.cave_low_noise.noise2d(world_x as f32, world_y as f32) * 20.0
-                                    + 111.0
-                                    - height as f32 * 0.6;
-                            let cave_high =
-                                self.cave_high_noise.noise2d(world_x as f32, world_y as f32) * 23.0
-                                    + lerp(57.0, height as f32, 0.3);
-
-                            for (z, block) in column.iter_mut().enumerate().take(CHUNK_HEIGHT) {
-                                *block = if z <= MAGMA_CORE {
-                                    Some(BlockType::Magma)
-                                } else if !biome.is_ocean()
-                                    && cave_low < z as f32
-                                    && (z as f32) < cave_high
-                                {
-                                    None
-                                } else if z <= 39 {
-                                    Some(BlockType::Basalt)
-                                } else if z <= height {
-                                    let base_stone = if z <= 47 {
-                                        BlockType::Basalt
-                                    } else {
-                                        BlockType::Stone
-                                    }; // TODO: noise
-                                    Some(if height.saturating_sub(z) < 5 {
-                                        biome.get_surface_block()
-                                    } else if cave_low < cave_high
-                                        && ((z as f32 - cave_low).abs() < 3.0
-                                            || (z as f32 - cave_high).abs() < 3.0)
-                                    {
-                                        Self::get_ore((world_x, world_y, z as i32), base_stone)
-                                    } else {
-                                        base_stone
-                                    })
-                                } else if z <= SEA {
-                                    Some(BlockType::Water)
-                                } else {
-                                    None
-                                };
-                            }
-                        }
-                    }
-                });
+    fn generate_chunk_blocks(&self, chunk_coords: ChunkCoords) -> Blocks {
+        if let WorldGeneratorKind::Flat { height, block } = self.kind {
+            return Self::generate_flat_blocks(height, block);
+        }
 
-                remainder = tail;
-                start_x += len;
+        let ChunkCoords {
+            cx: chunk_x,
+            cy: chunk_y,
+        } = chunk_coords;
+        let mut blocks = [[[None; CHUNK_HEIGHT]; CHUNK_WIDTH]; CHUNK_WIDTH];
+        let columns = self.chunk_columns(chunk_coords);
+
+        // single-threaded, the parallelism comes from the generation workers
+        for (x, plane) in blocks.iter_mut().enumerate() {
+            let world_x = (chunk_x * CHUNK_WIDTH as i32) + x as i32;
+
+            for (y, column) in plane.iter_mut().enumerate() {
+                let world_y = (chunk_y * CHUNK_WIDTH as i32) + y as i32;
+                let ColumnTerrain {
+                    height,
+                    surface_block,
+                    subsurface_block,
+                    subsurface_depth,
+                    cliff,
+                    cave_low,
+                    cave_high,
+                    ..
+                } = columns[x][y];
+
+                for (z, block) in column.iter_mut().enumerate().take(CHUNK_HEIGHT) {
+                    let cave_value = if (cave_low..=cave_high).contains(&z) {
+                        self.cave_noise_3d
+                            .noise3d(world_x as f32, world_y as f32, z as f32)
+                    } else {
+                        -1.0
+                    };
+
+                    *block = if z <= MAGMA_CORE {
+                        Some(BlockType::Magma)
+                    } else if cave_value > CAVE_THRESHOLD {
+                        None
+                    } else if z <= SOLID_BASALT_TOP {
+                        Some(BlockType::Basalt)
+                    } else if z <= height {
+                        let base_stone = if z <= BASALT_TOP {
+                            BlockType::Basalt
+                        } else {
+                            BlockType::Stone
+                        }; // TODO: noise
+                        let depth = height - z;
+                        Some(if !cliff && depth == 0 {
+                            surface_block
+                        } else if !cliff && depth < subsurface_depth {
+                            subsurface_block
+                        } else if cave_value > CAVE_THRESHOLD - CAVE_ORE_MARGIN {
+                            Self::get_ore(WorldCoords::new(world_x, world_y, z as i32), base_stone)
+                        } else {
+                            base_stone
+                        })
+                    } else if z <= SEA {
+                        Some(BlockType::Water)
+                    } else {
+                        None
+                    };
+                }
             }
-        });
+        }
+
+        self.generate_ore_veins(chunk_coords, &mut blocks);
+        self.generate_trees(chunk_coords, &columns, &mut blocks);
+        self.try_generate_dungeon(chunk_coords, &columns, &mut blocks);
 
         blocks
     }
 
-    pub fn generate_chunk_mesh(
-        &mut self,
-        (chunk_x, chunk_y): ChunkCoords,
-        camera_coords: ChunkCoords,
-    ) -> (Vec<Vertex>, Vec<u16>) {
-        self.load_chunk((chunk_x, chunk_y));
-        self.load_chunk((chunk_x, chunk_y + 1));
-        self.load_chunk((chunk_x, chunk_y - 1));
-        self.load_chunk((chunk_x + 1, chunk_y));
-        self.load_chunk((chunk_x - 1, chunk_y));
-
-        let chunk = self.get_chunk_if_loaded((chunk_x, chunk_y)).unwrap();
-
-        let adjacent = AdjacentChunks {
-            north: (chunk_distance(camera_coords, (chunk_x, chunk_y + 1)) < RENDER_DISTANCE)
-                .then(|| self.get_chunk_if_loaded((chunk_x, chunk_y + 1)).unwrap()),
-            south: (chunk_distance(camera_coords, (chunk_x, chunk_y - 1)) < RENDER_DISTANCE)
-                .then(|| self.get_chunk_if_loaded((chunk_x, chunk_y - 1)).unwrap()),
-            east: (chunk_distance(camera_coords, (chunk_x + 1, chunk_y)) < RENDER_DISTANCE)
-                .then(|| self.get_chunk_if_loaded((chunk_x + 1, chunk_y)).unwrap()),
-            west: (chunk_distance(camera_coords, (chunk_x - 1, chunk_y)) < RENDER_DISTANCE)
-                .then(|| self.get_chunk_if_loaded((chunk_x - 1, chunk_y)).unwrap()),
-        };
-
-        chunk.generate_mesh(&adjacent)
+    // every column is the same, the topmost block is always grass
+    fn generate_flat_blocks(height: usize, block: BlockType) -> Blocks {
+        let height = height.min(CHUNK_HEIGHT - 1);
+        let mut column = [None; CHUNK_HEIGHT];
+        column[..height].fill(Some(block));
+        column[height] = Some(BlockType::Grass);
+        [[column; CHUNK_WIDTH]; CHUNK_WIDTH]
     }
 
-    pub fn delete_center_block(&mut self, camera: &Camera) -> Option<(WorldCoords, BlockType)> {
-        let (_, world_coords, block) =
-            self.find_block_in_dir(camera.position(), camera.direction(), MAX_DELETE_DISTANCE)?;
-        self.delete_block(world_coords);
-        Some((world_coords, block))
+    // the biome blending reuses the column infos of the chunk
+    fn chunk_columns(&self, chunk_coords: ChunkCoords) -> ChunkColumns {
+        let origin_x = chunk_coords.cx * CHUNK_WIDTH as i32;
+        let origin_y = chunk_coords.cy * CHUNK_WIDTH as i32;
+        let infos: [[ColumnInfo; CHUNK_WIDTH]; CHUNK_WIDTH] = array::from_fn(|x| {
+            array::from_fn(|y| self.get_column_info(origin_x + x as i32, origin_y + y as i32))
+        });
+        // only the columns outside the chunk are sampled again
+        let biome_at = |x: i32, y: i32| {
+            if (0..CHUNK_WIDTH as i32).contains(&x) && (0..CHUNK_WIDTH as i32).contains(&y) {
+                infos[x as usize][y as usize].biome
+            } else {
+                self.determine_biome(&self.get_noise_values(origin_x + x, origin_y + y))
+            }
+        };
+        array::from_fn(|x| {
+            array::from_fn(|y| {
+                self.column_terrain(
+                    (origin_x + x as i32, origin_y + y as i32),
+                    infos[x][y],
+                    |dx, dy| biome_at(x as i32 + dx, y as i32 + dy),
+                )
+            })
+        })
     }
 
-    // TODO: update DDA to use the tree structure of Chunk
-    pub fn find_block_in_dir(
+    // `neighbor_biome` takes an offset from the column
+    fn column_terrain(
         &self,
-        pos: Vec3,
-        dir: Vec3,
-        max_distance: f32,
-    ) -> Option<(f32, WorldCoords, BlockType)> {
-        let start = pos;
-
-        let (mut ix, mut iy, mut iz) = camera_to_world_coords(start);
-
-        if self.get_block((ix, iy, iz)).is_some() {
-            return None;
+        (world_x, world_y): (i32, i32),
+        ColumnInfo {
+            height,
+            biome,
+            noise: noise_values,
+        }: ColumnInfo,
+        neighbor_biome: impl Fn(i32, i32) -> BiomeType,
+    ) -> ColumnTerrain {
+        let mut surface_block =
+            Self::blended_surface_block(world_x, world_y, biome, neighbor_biome);
+        if surface_block == BlockType::Grass
+            && biome.precipitation() == Precipitation::Snow
+            && self.is_above_snow_line(world_x, world_y, height)
+        {
+            surface_block = BlockType::Snow;
         }
+        ColumnTerrain {
+            height,
+            biome,
+            surface_block,
+            subsurface_block: biome.get_subsurface_block(),
+            subsurface_depth: biome.subsurface_depth(),
+            cliff: self.is_cliff(&noise_values),
+            cave_low: MAGMA_CORE + 1,
+            cave_high: if biome.is_ocean() { 0 } else { height },
+        }
+    }
 
-        let step_x = sign(dir.x);
-        let step_y = sign(dir.y);
-        let step_z = sign(dir.z);
-
-        let next_boundary = |i: i32, d: f32| -> f32 { (i + (d > 0.0) as i32) as f32 };
+    // snow gets denser over the blend band instead of starting at a straight line
+    fn is_above_snow_line(&self, world_x: i32, world_y: i32, height: usize) -> bool {
+        let t = inverse_lerp(
+            SNOW_LINE - SNOW_LINE_BLEND,
+            SNOW_LINE + SNOW_LINE_BLEND,
+            height as f32,
+        );
+        let roll = prf_i32x3_mod((world_x, world_y, SNOW_LINE_SALT), 1000) as f32 / 1000.0;
+        roll < smoothstep(0.0, 1.0, t)
+    }
 
-        let init_t_max = |i: i32, s: f32, d: f32| -> f32 {
-            if step_x != 0 {
-                (next_boundary(i, d) - s) / d
-            } else {
-                f32::INFINITY
-            }
+    // picks the surface block of a random neighbor, so borders fade instead of cutting straight
+    fn blended_surface_block(
+        world_x: i32,
+        world_y: i32,
+        biome: BiomeType,
+        neighbor_biome: impl Fn(i32, i32) -> BiomeType,
+    ) -> BlockType {
+        // the column's own biome counts twice, only the chosen neighbor is looked up
+        let choice = prf_i32x3_mod((world_x, world_y, BIOME_BLEND_SALT), 6) as usize;
+        let Some((dx, dy)) = [(1, 0), (-1, 0), (0, 1), (0, -1)].get(choice) else {
+            return biome.get_surface_block();
         };
+        let neighbor = neighbor_biome(dx * BIOME_BLEND_RADIUS, dy * BIOME_BLEND_RADIUS);
+        // no sea floor blocks on the shore and no shore blocks on the sea floor
+        if neighbor.is_ocean() == biome.is_ocean() {
+            neighbor.get_surface_block()
+        } else {
+            biome.get_surface_block()
+        }
+    }
 
-        let mut t_max_x = init_t_max(ix, start.x, dir.x);
-        let mut t_max_y = init_t_max(iy, start.y, dir.y);
-        let mut t_max_z = init_t_max(iz, start.z, dir.z);
+    // veins starting up to `radius` outside the chunk still reach into it
+    fn generate_ore_veins(&self, chunk_coords: ChunkCoords, blocks: &mut Blocks) {
+        let origin_x = chunk_coords.cx * CHUNK_WIDTH as i32;
+        let origin_y = chunk_coords.cy * CHUNK_WIDTH as i32;
 
-        let init_t_delta = |step: i32, d: f32| -> f32 {
-            if step != 0 {
-                (1.0 / d).abs()
-            } else {
-                f32::INFINITY
-            }
-        };
+        for vein in &ORE_VEINS {
+            let salt = self.ore_salt ^ ((vein.ore as i32) << 24);
 
-        let t_delta_x = init_t_delta(step_x, dir.x);
-        let t_delta_y = init_t_delta(step_y, dir.y);
-        let t_delta_z = init_t_delta(step_z, dir.z);
+            for x in -vein.radius..CHUNK_WIDTH as i32 + vein.radius {
+                for y in -vein.radius..CHUNK_WIDTH as i32 + vein.radius {
+                    for z in vein.min_z.max(MAGMA_CORE + 1)..=vein.max_z {
+                        let key = ((origin_x + x) ^ salt, origin_y + y, z as i32);
+                        if prf_i32x3_bool(key, 1, vein.chance) {
+                            Self::fill_vein(blocks, (x, y, z as i32), vein);
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-        let mut t = 0.0;
+    // flattened ellipsoid, only replacing stone
+    fn fill_vein(blocks: &mut Blocks, (cx, cy, cz): (i32, i32, i32), vein: &OreVein) {
+        let horizontal = vein.radius as f32 + 0.5;
+        let vertical = vein.radius as f32 * 0.5 + 0.5;
+
+        for x in (cx - vein.radius).max(0)..=(cx + vein.radius).min(CHUNK_WIDTH as i32 - 1) {
+            for y in (cy - vein.radius).max(0)..=(cy + vein.radius).min(CHUNK_WIDTH as i32 - 1) {
+                for z in cz - vein.radius..=cz + vein.radius {
+                    let dx = (x - cx) as f32 / horizontal;
+                    let dy = (y - cy) as f32 / horizontal;
+                    let dz = (z - cz) as f32 / vertical;
+                    if dx * dx + dy * dy + dz * dz > 1.0 {
+                        continue;
+                    }
 
-        while t <= max_distance {
-            if t_max_x < t_max_y {
-                if t_max_x < t_max_z {
-                    ix += step_x;
-                    t = t_max_x;
-                    t_max_x += t_delta_x;
-                } else {
-                    iz += step_z;
-                    t = t_max_z;
-                    t_max_z += t_delta_z;
-                }
-            } else {
-                if t_max_y < t_max_z {
-                    iy += step_y;
-                    t = t_max_y;
-                    t_max_y += t_delta_y;
-                } else {
-                    iz += step_z;
-                    t = t_max_z;
-                    t_max_z += t_delta_z;
+                    let block = &mut blocks[x as usize][y as usize][z as usize];
+                    if matches!(block, Some(BlockType::Stone | BlockType::Basalt)) {
+                        *block = Some(vein.ore);
+                    }
                 }
             }
+        }
+    }
 
-            if iz < 0 || iz >= CHUNK_HEIGHT as i32 {
-                return None;
-            }
+    // the room stays inside the chunk, between the magma core and the lowest ground above it
+    fn try_generate_dungeon(
+        &self,
+        chunk_coords: ChunkCoords,
+        columns: &ChunkColumns,
+        blocks: &mut Blocks,
+    ) {
+        let ChunkCoords { cx, cy } = chunk_coords;
+        let roll = |i: i32, m: usize| prf_i32x3_mod((cx ^ self.dungeon_salt, cy, i), m as u64);
+        if roll(0, DUNGEON_CHANCE as usize) != 0 {
+            return;
+        }
 
-            let world_coords = (ix, iy, iz);
-            if let Some(block) = self.get_block(world_coords) {
-                return Some((t, world_coords, block));
+        let x0 = roll(1, CHUNK_WIDTH - DUNGEON_WIDTH + 1) as usize;
+        let y0 = roll(2, CHUNK_WIDTH - DUNGEON_WIDTH + 1) as usize;
+        let ground = (x0..x0 + DUNGEON_WIDTH)
+            .flat_map(|x| (y0..y0 + DUNGEON_WIDTH).map(move |y| columns[x][y].height))
+            .min()
+            .unwrap_or(0);
+        // the ceiling stays under the ground
+        let lowest_floor = MAGMA_CORE + 1;
+        let Some(highest_floor) = ground.checked_sub(DUNGEON_HEIGHT) else {
+            return;
+        };
+        if highest_floor < lowest_floor {
+            return;
+        }
+        let z0 = lowest_floor + roll(3, highest_floor - lowest_floor + 1) as usize;
+
+        let is_border = |i: usize| i == 0 || i == DUNGEON_WIDTH - 1;
+        for (x, plane) in blocks[x0..x0 + DUNGEON_WIDTH].iter_mut().enumerate() {
+            for (y, column) in plane[y0..y0 + DUNGEON_WIDTH].iter_mut().enumerate() {
+                for (z, block) in column[z0..z0 + DUNGEON_HEIGHT].iter_mut().enumerate() {
+                    *block = if z == 0 {
+                        Some(BlockType::Basalt)
+                    } else if is_border(x) || is_border(y) || z == DUNGEON_HEIGHT - 1 {
+                        Some(BlockType::Stone)
+                    } else {
+                        None
+                    };
+                }
             }
         }
-
-        None
     }
 
-    pub fn get_block(&self, world_coords: WorldCoords) -> Option<BlockType> {
-        let (chunk_coords, block_coords) = split_coords(world_coords)?;
-        let chunk = self.get_chunk_if_loaded(chunk_coords)?;
-        chunk.get_block(block_coords)
+    // columns up to CANOPY_RADIUS outside the chunk can grow leaves into it,
+    // only those are sampled again
+    fn generate_trees(
+        &self,
+        chunk_coords: ChunkCoords,
+        columns: &ChunkColumns,
+        blocks: &mut Blocks,
+    ) {
+        for x in -CANOPY_RADIUS..CHUNK_WIDTH as i32 + CANOPY_RADIUS {
+            for y in -CANOPY_RADIUS..CHUNK_WIDTH as i32 + CANOPY_RADIUS {
+                let world_x = chunk_coords.cx * CHUNK_WIDTH as i32 + x;
+                let world_y = chunk_coords.cy * CHUNK_WIDTH as i32 + y;
+                let (height, biome) = if (0..CHUNK_WIDTH as i32).contains(&x)
+                    && (0..CHUNK_WIDTH as i32).contains(&y)
+                {
+                    let column = &columns[x as usize][y as usize];
+                    (column.height, column.biome)
+                } else {
+                    let ColumnInfo { height, biome, .. } = self.get_column_info(world_x, world_y);
+                    (height, biome)
+                };
+                self.try_place_tree((x, y), (world_x, world_y), height, biome, blocks);
+            }
+        }
     }
 
-    fn delete_block(&mut self, world_coords: WorldCoords) {
-        let Some((chunk_coords, block_coords)) = split_coords(world_coords) else {
+    fn try_place_tree(
+        &self,
+        (x, y): (i32, i32),
+        (world_x, world_y): (i32, i32),
+        height: usize,
+        biome: BiomeType,
+        blocks: &mut Blocks,
+    ) {
+        let Some(frequency) = biome.tree_frequency() else {
             return;
         };
+        let ground = (world_x, world_y, height as i32);
+        if height <= SEA || !prf_i32x3_bool(ground, 1, frequency) {
+            return;
+        }
 
-        let Some(chunk) = self.get_mut_chunk_if_loaded(chunk_coords) else {
+        // same test as generate_chunk_blocks, the ground may be carved away
+        let cave_value = self
+            .cave_noise_3d
+            .noise3d(world_x as f32, world_y as f32, height as f32);
+        if cave_value > CAVE_THRESHOLD {
+            return;
+        }
+
+        let trunk = 4 + prf_i32x3_mod((world_x, world_y, height as i32 + 1), 3) as i32;
+        let top = height as i32 + trunk;
+        if top + CANOPY_RADIUS >= CHUNK_HEIGHT as i32 {
             return;
+        }
+
+        // chunk-relative, may lie outside the chunk
+        let mut set = |(bx, by, bz): (i32, i32, i32), block: BlockType| {
+            if (0..CHUNK_WIDTH as i32).contains(&bx) && (0..CHUNK_WIDTH as i32).contains(&by) {
+                let cell = &mut blocks[bx as usize][by as usize][bz as usize];
+                if cell.is_none_or(|b| b == BlockType::Leaves) {
+                    *cell = Some(block);
+                }
+            }
         };
 
-        chunk.delete_block(block_coords);
-        self.deleted_blocks
-            .entry(chunk_coords)
-            .or_default()
-            .insert(block_coords);
+        for dx in -CANOPY_RADIUS..=CANOPY_RADIUS {
+            for dy in -CANOPY_RADIUS..=CANOPY_RADIUS {
+                for dz in -CANOPY_RADIUS..=CANOPY_RADIUS {
+                    if dx * dx + dy * dy + dz * dz <= CANOPY_RADIUS * CANOPY_RADIUS + 1 {
+                        set((x + dx, y + dy, top + dz), BlockType::Leaves);
+                    }
+                }
+            }
+        }
+        for z in height as i32 + 1..=top {
+            set((x, y, z), BlockType::Wood);
+        }
     }
 }